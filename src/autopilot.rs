@@ -0,0 +1,267 @@
+use super::{
+    gamelog::GameLog, name_of, player, CombatStats, Item, Map, Name, Position, RunState, TileType,
+    Viewshed,
+};
+use rltk::{BaseMap, Point};
+use specs::prelude::*;
+
+/// Moves the player for them, a step per turn, until something needs their attention.
+#[derive(PartialEq, Clone)]
+pub enum Autopilot {
+    Off,
+    // Items that were already in view when exploring started don't stop it
+    Explore { seen_items: Vec<Entity> },
+    // The map indices still to walk through, and the player's hp at the last step
+    Travel { path: Vec<usize>, hp: i32 },
+}
+
+pub fn is_active(ecs: &World) -> bool {
+    *ecs.fetch::<Autopilot>() != Autopilot::Off
+}
+
+pub fn stop(ecs: &mut World) {
+    *ecs.write_resource::<Autopilot>() = Autopilot::Off;
+}
+
+/// Starts auto-exploring and takes the first step, unless a monster is in sight.
+pub fn start_explore(ecs: &mut World) -> RunState {
+    if player::monster_in_view(ecs).is_some() {
+        let mut log = ecs.fetch_mut::<GameLog>();
+        log.entries
+            .insert(0, "You can't explore with enemies nearby.".to_string());
+        return RunState::AwaitingInput;
+    }
+
+    let seen_items = visible_items(ecs);
+    *ecs.write_resource::<Autopilot>() = Autopilot::Explore { seen_items };
+    step(ecs).unwrap_or(RunState::AwaitingInput)
+}
+
+/// Plans a path to `destination` and takes the first step along it. Only
+/// revealed floor can be travelled to, and not with a monster in sight.
+pub fn start_travel(ecs: &mut World, destination: Point) -> RunState {
+    let path = match travel_path(ecs, destination) {
+        Ok(path) => path,
+        Err(reason) => {
+            ecs.fetch_mut::<GameLog>()
+                .entries
+                .insert(0, reason.to_string());
+            return RunState::AwaitingInput;
+        }
+    };
+
+    let hp = player_hp(ecs);
+    *ecs.write_resource::<Autopilot>() = Autopilot::Travel { path, hp };
+    step(ecs).unwrap_or(RunState::AwaitingInput)
+}
+
+/// Takes the autopilot's next step. Returns `None`, and switches the autopilot
+/// off, when there is nothing more for it to do.
+pub fn step(ecs: &mut World) -> Option<RunState> {
+    let autopilot = (*ecs.fetch::<Autopilot>()).clone();
+    match autopilot {
+        Autopilot::Off => None,
+        Autopilot::Explore { seen_items } => {
+            let next = match interruption(ecs, &seen_items) {
+                Some(reason) => Err(reason),
+                None => {
+                    explore_step(ecs).ok_or_else(|| "There is nothing left to explore.".to_string())
+                }
+            };
+
+            match next {
+                Ok((delta_x, delta_y)) => {
+                    player::try_move_player(delta_x, delta_y, ecs);
+                    Some(RunState::PlayerTurn)
+                }
+                Err(reason) => {
+                    ecs.fetch_mut::<GameLog>().entries.insert(0, reason);
+                    stop(ecs);
+                    None
+                }
+            }
+        }
+        Autopilot::Travel { mut path, hp } => {
+            if let Some(reason) = travel_interruption(ecs, &path, hp) {
+                ecs.fetch_mut::<GameLog>().entries.insert(0, reason);
+                stop(ecs);
+                return None;
+            }
+
+            let next = path[0];
+            let (delta_x, delta_y) = {
+                let map = ecs.fetch::<Map>();
+                let player_pos = ecs.fetch::<Point>();
+                let (x, y) = (next as i32 % map.width, next as i32 / map.width);
+                (x - player_pos.x, y - player_pos.y)
+            };
+            player::try_move_player(delta_x, delta_y, ecs);
+
+            // Opening a door on the way takes a turn without moving
+            let arrived_at_next = {
+                let map = ecs.fetch::<Map>();
+                let player_pos = ecs.fetch::<Point>();
+                map.xy_idx(player_pos.x, player_pos.y) == next
+            };
+            if arrived_at_next {
+                path.remove(0);
+            }
+
+            // Arriving switches the autopilot off, but the last step still takes a turn
+            *ecs.write_resource::<Autopilot>() = if path.is_empty() {
+                Autopilot::Off
+            } else {
+                Autopilot::Travel {
+                    path,
+                    hp: player_hp(ecs),
+                }
+            };
+            Some(RunState::PlayerTurn)
+        }
+    }
+}
+
+// The steps from the player to `destination`, not counting the tile they're on
+fn travel_path(ecs: &World, destination: Point) -> Result<Vec<usize>, &'static str> {
+    let mut map = ecs.write_resource::<Map>();
+    let player_pos = ecs.fetch::<Point>();
+
+    let in_bounds = destination.x >= 0
+        && destination.x < map.width
+        && destination.y >= 0
+        && destination.y < map.height;
+    if !in_bounds {
+        return Err("You can't travel there.");
+    }
+    let destination_idx = map.xy_idx(destination.x, destination.y);
+    if !map.revealed_tiles[destination_idx] || map.tiles[destination_idx] == TileType::Wall {
+        return Err("You can't travel there.");
+    }
+    if destination == *player_pos {
+        return Err("You are already there.");
+    }
+    if player::monster_in_view(ecs).is_some() {
+        return Err("You can't travel with enemies nearby.");
+    }
+
+    let path = rltk::a_star_search(
+        map.xy_idx(player_pos.x, player_pos.y) as i32,
+        destination_idx as i32,
+        &mut *map,
+    );
+    if !path.success || path.steps.len() < 2 {
+        return Err("You can't find a way there.");
+    }
+    Ok(path.steps[1..].iter().map(|idx| *idx as usize).collect())
+}
+
+// Why travelling should stop before the next step on `path`, if it should
+fn travel_interruption(ecs: &World, path: &[usize], hp: i32) -> Option<String> {
+    if let Some(monster) = player::monster_in_view(ecs) {
+        return Some(format!(
+            "You spot a {}.",
+            name_of(&ecs.read_storage::<Name>(), monster)
+        ));
+    }
+    if player_hp(ecs) < hp {
+        return Some("You stop travelling; something hurt you.".to_string());
+    }
+
+    let map = ecs.fetch::<Map>();
+    let player_pos = ecs.fetch::<Point>();
+    match path.first() {
+        Some(next) if map.blocked[*next] => Some("Something is in the way.".to_string()),
+        Some(next) => {
+            // Knocked off the path, e.g. by a teleport trap
+            let (x, y) = (*next as i32 % map.width, *next as i32 / map.width);
+            let distance = i32::max((x - player_pos.x).abs(), (y - player_pos.y).abs());
+            if distance == 1 {
+                None
+            } else {
+                Some("You have lost your way.".to_string())
+            }
+        }
+        None => Some("Something is in the way.".to_string()),
+    }
+}
+
+fn player_hp(ecs: &World) -> i32 {
+    let player_entity = ecs.fetch::<Entity>();
+    ecs.read_storage::<CombatStats>()
+        .get(*player_entity)
+        .map_or(0, |stats| stats.hp)
+}
+
+/// The direction of the next step towards the nearest tile the player hasn't
+/// seen yet, or `None` if every tile they can reach is already revealed.
+pub fn explore_step(ecs: &World) -> Option<(i32, i32)> {
+    let map = ecs.fetch::<Map>();
+    let player_pos = ecs.fetch::<Point>();
+
+    let targets: Vec<i32> = (0..map.tiles.len())
+        .filter(|idx| !map.revealed_tiles[*idx] && map.tiles[*idx] != TileType::Wall)
+        .map(|idx| idx as i32)
+        .collect();
+    if targets.is_empty() {
+        return None;
+    }
+
+    let dijkstra = rltk::DijkstraMap::new(
+        map.width,
+        map.height,
+        &targets,
+        &*map,
+        map.dijkstra_max_depth(),
+    );
+    let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+    let mut best = (player_idx, dijkstra.map[player_idx]);
+    if best.1 == f32::MAX {
+        return None;
+    }
+
+    for (exit, _cost) in map.get_available_exits(player_idx as i32) {
+        let exit = exit as usize;
+        if dijkstra.map[exit] < best.1 {
+            best = (exit, dijkstra.map[exit]);
+        }
+    }
+
+    if best.0 == player_idx {
+        return None;
+    }
+    let (x, y) = (best.0 as i32 % map.width, best.0 as i32 / map.width);
+    Some((x - player_pos.x, y - player_pos.y))
+}
+
+// Why the autopilot should hand control back, if it should
+fn interruption(ecs: &World, seen_items: &[Entity]) -> Option<String> {
+    let names = ecs.read_storage::<Name>();
+
+    if let Some(monster) = player::monster_in_view(ecs) {
+        return Some(format!("You spot a {}.", name_of(&names, monster)));
+    }
+
+    visible_items(ecs)
+        .into_iter()
+        .find(|item| !seen_items.contains(item))
+        .map(|item| format!("You see a {}.", name_of(&names, item)))
+}
+
+// Items lying on the floor where the player can see them
+fn visible_items(ecs: &World) -> Vec<Entity> {
+    let player_entity = ecs.fetch::<Entity>();
+    let viewsheds = ecs.read_storage::<Viewshed>();
+    let items = ecs.read_storage::<Item>();
+    let positions = ecs.read_storage::<Position>();
+    let entities = ecs.entities();
+
+    let viewshed = match viewsheds.get(*player_entity) {
+        Some(viewshed) => viewshed,
+        None => return Vec::new(),
+    };
+    (&entities, &items, &positions)
+        .join()
+        .filter(|(_entity, _item, pos)| viewshed.visible_tiles.contains(&Point::new(pos.x, pos.y)))
+        .map(|(entity, _item, _pos)| entity)
+        .collect()
+}
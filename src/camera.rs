@@ -0,0 +1,91 @@
+use super::{Hidden, Map, Position, Renderable, TileType};
+use rltk::{Console, Point, Rltk, RGB};
+use specs::prelude::*;
+
+/// Size of the part of the screen the map is drawn in; the ui panel sits below it.
+pub const VIEW_WIDTH: i32 = 80;
+pub const VIEW_HEIGHT: i32 = 43;
+
+// The map tile shown in the top left corner when `center` is in the middle of the view
+fn view_origin(center: Point) -> Point {
+    Point::new(center.x - VIEW_WIDTH / 2, center.y - VIEW_HEIGHT / 2)
+}
+
+/// The map position under a screen cell, with the view centred on the player.
+/// It may be off the map.
+pub fn screen_to_world(ecs: &World, screen_x: i32, screen_y: i32) -> Point {
+    let origin = view_origin(*ecs.fetch::<Point>());
+    Point::new(origin.x + screen_x, origin.y + screen_y)
+}
+
+/// The screen cell a map position is drawn in, or `None` if it's out of view.
+pub fn world_to_screen(ecs: &World, pos: Point) -> Option<Point> {
+    let origin = view_origin(*ecs.fetch::<Point>());
+    let (x, y) = (pos.x - origin.x, pos.y - origin.y);
+    if (0..VIEW_WIDTH).contains(&x) && (0..VIEW_HEIGHT).contains(&y) {
+        Some(Point::new(x, y))
+    } else {
+        None
+    }
+}
+
+/// Draws the part of the map around the player, and the entities on it they can see
+/// (less anything still hidden).
+pub fn render_camera(ecs: &World, ctx: &mut Rltk) {
+    let map = ecs.fetch::<Map>();
+    let origin = view_origin(*ecs.fetch::<Point>());
+    draw_tiles(&map, origin, ctx);
+
+    let positions = ecs.read_storage::<Position>();
+    let renderables = ecs.read_storage::<Renderable>();
+    let hidden = ecs.read_storage::<Hidden>();
+
+    // Highest render order first, so e.g. monsters stand on top of items
+    let mut data = (&positions, &renderables, !&hidden)
+        .join()
+        .map(|(pos, render, _hidden)| (pos, render))
+        .collect::<Vec<_>>();
+    data.sort_by_key(|(_pos, render)| std::cmp::Reverse(render.render_order));
+    for (pos, render) in data.iter() {
+        let idx = map.xy_idx(pos.x, pos.y);
+        if map.visible_tiles[idx] {
+            if let Some(screen) = world_to_screen(ecs, Point::new(pos.x, pos.y)) {
+                ctx.set(screen.x, screen.y, render.fg, render.bg, render.glyph);
+            }
+        }
+    }
+}
+
+/// Draws a map on its own, centred on its middle, for the map generation visualizer.
+pub fn render_debug_map(map: &Map, ctx: &mut Rltk) {
+    let origin = view_origin(Point::new(map.width / 2, map.height / 2));
+    draw_tiles(map, origin, ctx);
+}
+
+fn draw_tiles(map: &Map, origin: Point, ctx: &mut Rltk) {
+    for screen_y in 0..VIEW_HEIGHT {
+        for screen_x in 0..VIEW_WIDTH {
+            let (x, y) = (origin.x + screen_x, origin.y + screen_y);
+            if x < 0 || x >= map.width || y < 0 || y >= map.height {
+                continue;
+            }
+
+            let idx = map.xy_idx(x, y);
+            if map.revealed_tiles[idx] {
+                let (glyph, mut fg) = tile_glyph(map.tiles[idx]);
+                if !map.visible_tiles[idx] {
+                    fg = fg.to_greyscale()
+                }
+                ctx.set(screen_x, screen_y, fg, RGB::from_f32(0., 0., 0.), glyph);
+            }
+        }
+    }
+}
+
+fn tile_glyph(tile: TileType) -> (u8, RGB) {
+    match tile {
+        TileType::Floor => (rltk::to_cp437('.'), RGB::from_f32(0.0, 0.5, 0.5)),
+        TileType::Wall => (rltk::to_cp437('#'), RGB::from_f32(0., 1.0, 0.)),
+        TileType::DownStairs => (rltk::to_cp437('>'), RGB::from_f32(0., 1.0, 1.0)),
+    }
+}
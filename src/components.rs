@@ -1,41 +1,53 @@
 use rltk::RGB;
+use serde::{Deserialize, Serialize};
+use specs::error::NoError;
 use specs::prelude::*;
+use specs::saveload::{ConvertSaveload, Marker};
 
-#[derive(Component)]
+#[derive(Component, Serialize, Deserialize, Clone)]
 pub struct Position {
     pub x: i32,
     pub y: i32,
 }
 
-#[derive(Component)]
+#[derive(Component, Serialize, Deserialize, Clone)]
 pub struct Renderable {
     pub glyph: u8,
     pub fg: RGB,
     pub bg: RGB,
+    // Lower orders are drawn last, on top of anything sharing the tile
+    pub render_order: i32,
 }
 
-#[derive(Component, Debug)]
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
 pub struct Player {}
 
-#[derive(Component)]
+#[derive(Component, Serialize, Deserialize, Clone)]
 pub struct Viewshed {
     pub visible_tiles: Vec<rltk::Point>,
     pub range: i32,
     pub dirty: bool,
 }
 
-#[derive(Component, Debug)]
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
 pub struct Monster {}
 
-#[derive(Component, Debug)]
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
 pub struct Name {
     pub name: String,
 }
 
-#[derive(Component)]
+/// What to call `entity` in the log, for anything that somehow has no name.
+pub fn name_of(names: &ReadStorage<Name>, entity: Entity) -> String {
+    names
+        .get(entity)
+        .map_or("something".to_string(), |name| name.name.clone())
+}
+
+#[derive(Component, Serialize, Deserialize, Clone)]
 pub struct BlocksTile {}
 
-#[derive(Component, Debug)]
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
 pub struct CombatStats {
     pub max_hp: i32,
     pub hp: i32,
@@ -43,12 +55,267 @@ pub struct CombatStats {
     pub power: i32,
 }
 
-#[derive(Component, Debug)]
+// Holds an Entity, so it has to go through ConvertSaveload to have the
+// reference remapped to the marker id on save and back on load.
+#[derive(Component, ConvertSaveload, Clone, Debug)]
 pub struct WantsToMelee {
     pub target: Entity,
 }
 
-#[derive(Component, Debug)]
+// Every hit taken this turn, so several attackers can damage the same target,
+// along with who dealt it (if anyone) so the killing blow can be credited.
+// Only lives for the turn it was dealt in, so it is never saved.
+#[derive(Component, Clone, Debug)]
 pub struct SufferDamage {
+    pub amount: Vec<(i32, Option<Entity>)>,
+}
+
+impl SufferDamage {
+    pub fn new_damage(
+        store: &mut WriteStorage<SufferDamage>,
+        victim: Entity,
+        amount: i32,
+        source: Option<Entity>,
+    ) {
+        if let Some(suffering) = store.get_mut(victim) {
+            suffering.amount.push((amount, source));
+        } else {
+            let dmg = SufferDamage {
+                amount: vec![(amount, source)],
+            };
+            store
+                .insert(victim, dmg)
+                .expect("@ERROR: Unable to insert damage");
+        }
+    }
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct Item {}
+
+#[derive(Component, ConvertSaveload, Clone, Debug)]
+pub struct InBackpack {
+    pub owner: Entity,
+}
+
+#[derive(Component, ConvertSaveload, Clone, Debug)]
+pub struct WantsToPickupItem {
+    pub collected_by: Entity,
+    pub item: Entity,
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct Consumable {}
+
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct ProvidesHealing {
+    pub heal_amount: i32,
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct Ranged {
+    pub range: i32,
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct InflictsDamage {
+    pub damage: i32,
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct AreaOfEffect {
+    pub radius: i32,
+}
+
+// On an item, how long it confuses its targets for. On a creature, how many
+// more turns it stays confused.
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct Confusion {
+    pub turns: i32,
+}
+
+// `target` is only set for ranged items; everything else is used on the user
+#[derive(Component, ConvertSaveload, Clone, Debug)]
+pub struct WantsToUseItem {
+    pub item: Entity,
+    pub target: Option<rltk::Point>,
+}
+
+#[derive(Component, ConvertSaveload, Clone, Debug)]
+pub struct WantsToDropItem {
+    pub item: Entity,
+}
+
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize, Debug)]
+pub enum EquipmentSlot {
+    Melee,
+    Shield,
+    Armor,
+    Head,
+    Ring,
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct Equippable {
+    pub slot: EquipmentSlot,
+}
+
+#[derive(Component, ConvertSaveload, Clone, Debug)]
+pub struct Equipped {
+    pub owner: Entity,
+    pub slot: EquipmentSlot,
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct MeleePowerBonus {
+    pub power: i32,
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct DefenseBonus {
+    pub defense: i32,
+}
+
+#[derive(Component, ConvertSaveload, Clone, Debug)]
+pub struct WantsToRemoveItem {
+    pub item: Entity,
+}
+
+// How quickly something acts: it gains `speed` energy every tick, and takes an
+// action whenever it has saved up enough
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct Energy {
+    pub speed: i32,
+    pub energy: i32,
+}
+
+// Marks whoever gets to act this tick
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct MyTurn {}
+
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize, Debug)]
+pub enum HungerState {
+    WellFed,
+    Normal,
+    Hungry,
+    Starving,
+}
+
+// Counts down the turns left in the current hunger state
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct HungerClock {
+    pub state: HungerState,
+    pub duration: i32,
+}
+
+// Eating this resets the eater's hunger clock to well fed
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct ProvidesFood {}
+
+// Progress of a creature that levels up from kills
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct Experience {
+    pub level: i32,
+    pub xp: i32,
+}
+
+// Experience awarded to whoever lands the killing blow
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct XpValue {
     pub amount: i32,
 }
+
+// Stands in for a wall, for sight, until it's opened
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct Door {
+    pub open: bool,
+}
+
+// Nothing can see through a tile with one of these on it
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct BlocksVisibility {}
+
+// Queued by walking into a closed door; the turn is spent opening it
+#[derive(Component, ConvertSaveload, Clone, Debug)]
+pub struct WantsToOpenDoor {
+    pub door: Entity,
+}
+
+// Not drawn or named under the mouse until the player spots it
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct Hidden {}
+
+// Goes off when something walks onto its tile
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct EntryTrigger {}
+
+// Disarmed (deleted) after going off once
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct SingleActivation {}
+
+// Moves whoever sets it off to a random spot on the level
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct TeleportsTarget {}
+
+// Sends every monster within `radius` to see what set it off
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct SoundsAlarm {
+    pub radius: i32,
+}
+
+// How a kind of monster behaves, from its raw definition
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct AiBehaviour {
+    // Wanders about when idle, rather than standing still
+    pub wanders: bool,
+    // Runs away once hp drops below this percentage of max hp; 0 never runs
+    pub flee_below: i32,
+    // Turns spent looking for the player after losing sight of them; 0 gives up at once
+    pub search_turns: i32,
+}
+
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize, Debug)]
+pub enum AiState {
+    Idle,
+    // Remembers where the player was last seen, to search there if they're lost
+    Chase { x: i32, y: i32 },
+    // Heading for a spot the player might be, for up to `turns` more turns
+    Search { x: i32, y: i32, turns: i32 },
+    Flee,
+}
+
+// What a monster is currently up to, see `MonsterAI`
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct MonsterAiState {
+    pub state: AiState,
+}
+
+// Who a creature sides with; how factions react to each other is in the raws
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct Faction {
+    pub name: String,
+}
+
+// The last thing to hurt a creature, which it fights back against even if its
+// faction would normally leave them alone
+#[derive(Component, ConvertSaveload, Clone, Debug)]
+pub struct Provoked {
+    pub by: Entity,
+}
+
+// Marks whoever changed tiles this turn, so entry triggers can check them
+#[derive(Component, Clone, Debug)]
+pub struct EntityMoved {}
+
+// Special component that exists to help serialize the game data
+pub struct SerializeMe;
+
+// Temporary entity that carries the non-component world state (map, log, stats
+// & a seed for the rng) through the save file
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct SerializationHelper {
+    pub map: super::map::Map,
+    pub log: Vec<String>,
+    pub rng_seed: u64,
+    pub stats: super::run_stats::RunStats,
+}
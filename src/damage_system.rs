@@ -1,25 +1,105 @@
-use super::{gamelog::GameLog, CombatStats, Name, Player, SufferDamage};
-use rltk::console;
+use super::{
+    gamelog::GameLog, run_stats::RunStats, CombatStats, Experience, Faction, Name, Player,
+    Provoked, RunState, SufferDamage, XpValue,
+};
 use specs::prelude::*;
 
+/// Experience needed to go from `level` to the next one.
+pub fn xp_to_next_level(level: i32) -> i32 {
+    level * 100
+}
+
+/// Max hp gained on every level up.
+const LEVEL_UP_HP: i32 = 10;
+
 pub struct DamageSystem {}
 
 impl<'a> System<'a> for DamageSystem {
+    #[allow(clippy::type_complexity)]
     type SystemData = (
+        ReadExpect<'a, Entity>, // Player entity resource
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, RunStats>,
+        Entities<'a>,
         WriteStorage<'a, CombatStats>,
         WriteStorage<'a, SufferDamage>,
+        WriteStorage<'a, Experience>,
+        ReadStorage<'a, XpValue>,
+        ReadStorage<'a, Faction>,
+        WriteStorage<'a, Provoked>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut stats, mut damage) = data;
+        let (
+            player_entity,
+            mut log,
+            mut run_stats,
+            entities,
+            mut stats,
+            mut damage,
+            mut experience,
+            xp_values,
+            factions,
+            mut provoked,
+        ) = data;
 
-        // Apply damage to combat stats hp
-        for (mut stats, damage) in (&mut stats, &damage).join() {
-            stats.hp -= damage.amount;
+        // Apply damage to combat stats hp, noting who landed each killing blow
+        let mut xp_awards: Vec<(Entity, i32)> = Vec::new();
+        for (victim, stats, damage) in (&entities, &mut stats, &damage).join() {
+            for (amount, source) in damage.amount.iter() {
+                let was_alive = stats.hp > 0;
+                stats.hp -= amount;
+
+                // Creatures don't ignore whoever just hurt them
+                if let Some(attacker) = source {
+                    if *attacker != victim && factions.get(victim).is_some() {
+                        provoked
+                            .insert(victim, Provoked { by: *attacker })
+                            .expect("@ERROR: Unable to insert provoked");
+                    }
+                }
+
+                if was_alive && stats.hp < 1 {
+                    if let Some(killer) = source {
+                        // Only the player's own kills go on their record
+                        if *killer == *player_entity {
+                            run_stats.kills += 1;
+                        }
+                        if let Some(xp) = xp_values.get(victim) {
+                            xp_awards.push((*killer, xp.amount));
+                        }
+                    }
+                }
+            }
         }
 
         // Now that the damage is applied, remove the msg component
         damage.clear();
+
+        // Credit the killers, levelling them up as they pass each threshold
+        for (killer, amount) in xp_awards.iter() {
+            if let (Some(progress), Some(stats)) =
+                (experience.get_mut(*killer), stats.get_mut(*killer))
+            {
+                progress.xp += amount;
+                while progress.xp >= xp_to_next_level(progress.level) {
+                    progress.xp -= xp_to_next_level(progress.level);
+                    progress.level += 1;
+
+                    stats.max_hp += LEVEL_UP_HP;
+                    stats.hp = stats.max_hp;
+                    stats.power += 1;
+                    stats.defense += 1;
+
+                    if *killer == *player_entity {
+                        log.entries.insert(
+                            0,
+                            format!("Congratulations, you are now level {}!", progress.level),
+                        );
+                    }
+                }
+            }
+        }
     }
 }
 
@@ -48,7 +128,10 @@ pub fn delete_the_dead(ecs: &mut World) {
                         // This is NOT the player, queue for deletion
                         dead.push(entity);
                     }
-                    Some(_) => console::log("You dead"),
+                    Some(_) => {
+                        let mut runstate = ecs.write_resource::<RunState>();
+                        *runstate = RunState::GameOver;
+                    }
                 }
             }
         }
@@ -58,4 +141,16 @@ pub fn delete_the_dead(ecs: &mut World) {
         ecs.delete_entity(victim)
             .expect("@ERROR: Unable to delete dead entity");
     }
+
+    // Forget grudges against the dead, so nothing refers to a deleted entity
+    let entities = ecs.entities();
+    let mut provoked = ecs.write_storage::<Provoked>();
+    let forgiven: Vec<Entity> = (&entities, &provoked)
+        .join()
+        .filter(|(_entity, provoked)| !entities.is_alive(provoked.by))
+        .map(|(entity, _provoked)| entity)
+        .collect();
+    for entity in forgiven {
+        provoked.remove(entity);
+    }
 }
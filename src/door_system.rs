@@ -0,0 +1,54 @@
+use super::{BlocksVisibility, Door, Map, Position, Renderable, Viewshed, WantsToOpenDoor};
+use specs::prelude::*;
+
+pub struct DoorSystem {}
+
+impl<'a> System<'a> for DoorSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteExpect<'a, Map>,
+        WriteStorage<'a, WantsToOpenDoor>,
+        WriteStorage<'a, Door>,
+        WriteStorage<'a, BlocksVisibility>,
+        WriteStorage<'a, Renderable>,
+        WriteStorage<'a, Viewshed>,
+        ReadStorage<'a, Position>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            mut map,
+            mut wants_to_open,
+            mut doors,
+            mut blocks_visibility,
+            mut renderables,
+            mut viewsheds,
+            positions,
+        ) = data;
+
+        let mut opened_any = false;
+        for intent in wants_to_open.join() {
+            if let Some(door) = doors.get_mut(intent.door) {
+                door.open = true;
+                blocks_visibility.remove(intent.door);
+                if let Some(render) = renderables.get_mut(intent.door) {
+                    render.glyph = rltk::to_cp437('/');
+                }
+                // Don't wait for the next indexing pass, so sight goes through straight away
+                if let Some(pos) = positions.get(intent.door) {
+                    let idx = map.xy_idx(pos.x, pos.y);
+                    map.view_blocked[idx] = false;
+                }
+                opened_any = true;
+            }
+        }
+        wants_to_open.clear();
+
+        // Whoever could see the door may now see past it
+        if opened_any {
+            for viewshed in (&mut viewsheds).join() {
+                viewshed.dirty = true;
+            }
+        }
+    }
+}
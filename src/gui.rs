@@ -1,5 +1,9 @@
-use super::{gamelog::GameLog, CombatStats, Map, Name, Player, Position};
-use rltk::{Console, Point, Rltk, RGB};
+use super::{
+    camera, damage_system::xp_to_next_level, gamelog::GameLog, run_stats::RunStats,
+    saveload_system, CombatStats, EquipmentSlot, Equipped, Experience, Hidden, HungerClock,
+    HungerState, InBackpack, Map, Name, Player, Position, RunState, State, Viewshed,
+};
+use rltk::{Console, Point, Rltk, VirtualKeyCode, RGB};
 use specs::prelude::*;
 
 pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
@@ -12,6 +16,17 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         RGB::named(rltk::BLACK),
     );
 
+    // Display depth
+    let map = ecs.fetch::<Map>();
+    let depth = format!("Depth: {}", map.depth);
+    ctx.print_color(
+        2,
+        43,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        &depth,
+    );
+
     // Display health
     let combat_stats = ecs.read_storage::<CombatStats>();
     let players = ecs.read_storage::<Player>();
@@ -38,14 +53,45 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         );
     }
 
+    // Display level & progress towards the next one
+    let experience = ecs.read_storage::<Experience>();
+    for (_player, progress) in (&players, &experience).join() {
+        let level = format!(
+            "Level: {}  XP: {} / {}",
+            progress.level,
+            progress.xp,
+            xp_to_next_level(progress.level)
+        );
+        ctx.print_color(
+            2,
+            49,
+            RGB::named(rltk::YELLOW),
+            RGB::named(rltk::BLACK),
+            &level,
+        );
+    }
+
+    // Display hunger, unless there's nothing to say about it
+    let hunger_clocks = ecs.read_storage::<HungerClock>();
+    for (_player, clock) in (&players, &hunger_clocks).join() {
+        let status = match clock.state {
+            HungerState::WellFed => Some(("Well Fed", RGB::named(rltk::GREEN))),
+            HungerState::Normal => None,
+            HungerState::Hungry => Some(("Hungry", RGB::named(rltk::ORANGE))),
+            HungerState::Starving => Some(("Starving", RGB::named(rltk::RED))),
+        };
+        if let Some((text, color)) = status {
+            ctx.print_color(70, 49, color, RGB::named(rltk::BLACK), text);
+        }
+    }
+
     // Display log
     let log = ecs.fetch::<GameLog>();
-    let mut y = 44;
-    for s in log.entries.iter() {
+    for (i, s) in log.entries.iter().enumerate() {
+        let y = 44 + i as i32;
         if y < 49 {
             ctx.print(2, y, &s.to_string());
         }
-        y += 1;
     }
 
     // Draw mouse cursor
@@ -60,18 +106,25 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
     let map = ecs.fetch::<Map>();
     let names = ecs.read_storage::<Name>();
     let positions = ecs.read_storage::<Position>();
+    let hidden = ecs.read_storage::<Hidden>();
 
     let mouse_pos = ctx.mouse_pos();
-    // Exit if invalid mouse position
-    if mouse_pos.0 >= map.width || mouse_pos.1 >= map.height {
+    let mouse_map_pos = camera::screen_to_world(ecs, mouse_pos.0, mouse_pos.1);
+    // Exit if the mouse isn't over the map
+    if mouse_pos.1 >= camera::VIEW_HEIGHT
+        || mouse_map_pos.x < 0
+        || mouse_map_pos.x >= map.width
+        || mouse_map_pos.y < 0
+        || mouse_map_pos.y >= map.height
+    {
         return;
     }
 
     let mut tooltip: Vec<String> = Vec::new();
 
     // Add tooltips for entity under mouse
-    for (name, position) in (&names, &positions).join() {
-        if position.x == mouse_pos.0 && position.y == mouse_pos.1 {
+    for (name, position, _hidden) in (&names, &positions, !&hidden).join() {
+        if position.x == mouse_map_pos.x && position.y == mouse_map_pos.y {
             tooltip.push(name.name.to_string());
         }
     }
@@ -91,8 +144,8 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
         if mouse_pos.0 > 40 {
             let arrow_pos = Point::new(mouse_pos.0 - 2, mouse_pos.1);
             let left_x = mouse_pos.0 - width;
-            let mut y = mouse_pos.1;
-            for s in tooltip.iter() {
+            for (i, s) in tooltip.iter().enumerate() {
+                let y = mouse_pos.1 + i as i32;
                 // Print tool tip text
                 ctx.print_color(
                     left_x,
@@ -110,11 +163,9 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
                         y,
                         RGB::named(rltk::WHITE),
                         RGB::named(rltk::GREY),
-                        &" ".to_string(),
+                        " ",
                     );
                 }
-
-                y += 1;
             }
 
             // And finally the arrow
@@ -123,13 +174,13 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
                 arrow_pos.y,
                 RGB::named(rltk::WHITE),
                 RGB::named(rltk::GREY),
-                &"->".to_string(),
+                "->",
             );
         } else {
             let arrow_pos = Point::new(mouse_pos.0 + 1, mouse_pos.1);
             let left_x = mouse_pos.0 + 3;
-            let mut y = mouse_pos.1;
-            for s in tooltip.iter() {
+            for (i, s) in tooltip.iter().enumerate() {
+                let y = mouse_pos.1 + i as i32;
                 ctx.print_color(
                     left_x,
                     y,
@@ -144,18 +195,420 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
                         y,
                         RGB::named(rltk::WHITE),
                         RGB::named(rltk::GREY),
-                        &" ".to_string(),
+                        " ",
                     );
                 }
-                y += 1;
             }
             ctx.print_color(
                 arrow_pos.x,
                 arrow_pos.y,
                 RGB::named(rltk::WHITE),
                 RGB::named(rltk::GREY),
-                &"<-".to_string(),
+                "<-",
             );
         }
     }
 }
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum ItemMenuResult {
+    Cancel,
+    NoResponse,
+    Selected,
+}
+
+/// Draws a boxed list of `items`, each prefixed with the letter that selects it.
+fn item_menu(
+    ctx: &mut Rltk,
+    title: &str,
+    items: &[(Entity, String)],
+) -> (ItemMenuResult, Option<Entity>) {
+    let count = items.len() as i32;
+
+    let y = 25 - (count / 2);
+    ctx.draw_box(
+        15,
+        y - 2,
+        31,
+        count + 3,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        18,
+        y - 2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        title,
+    );
+    ctx.print_color(
+        18,
+        y + count + 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "ESCAPE to cancel",
+    );
+
+    for (j, (_entity, name)) in items.iter().enumerate() {
+        let y = y + j as i32;
+        ctx.set(
+            17,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            rltk::to_cp437('('),
+        );
+        ctx.set(
+            18,
+            y,
+            RGB::named(rltk::YELLOW),
+            RGB::named(rltk::BLACK),
+            97 + j as u8,
+        );
+        ctx.set(
+            19,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            rltk::to_cp437(')'),
+        );
+        ctx.print(21, y, name);
+    }
+
+    match ctx.key {
+        None => (ItemMenuResult::NoResponse, None),
+        Some(key) => match key {
+            VirtualKeyCode::Escape => (ItemMenuResult::Cancel, None),
+            _ => {
+                let selection = rltk::letter_to_option(key);
+                if selection > -1 && selection < count {
+                    return (ItemMenuResult::Selected, Some(items[selection as usize].0));
+                }
+                (ItemMenuResult::NoResponse, None)
+            }
+        },
+    }
+}
+
+/// Entities & names of everything in the player's backpack.
+fn player_backpack(ecs: &World) -> Vec<(Entity, String)> {
+    let player_entity = ecs.fetch::<Entity>();
+    let names = ecs.read_storage::<Name>();
+    let backpack = ecs.read_storage::<InBackpack>();
+    let entities = ecs.entities();
+
+    (&entities, &backpack, &names)
+        .join()
+        .filter(|(_entity, pack, _name)| pack.owner == *player_entity)
+        .map(|(entity, _pack, name)| (entity, name.name.to_string()))
+        .collect()
+}
+
+/// Entities, slots & names of everything the player has equipped.
+fn player_equipment(ecs: &World) -> Vec<(Entity, EquipmentSlot, String)> {
+    let player_entity = ecs.fetch::<Entity>();
+    let names = ecs.read_storage::<Name>();
+    let equipped = ecs.read_storage::<Equipped>();
+    let entities = ecs.entities();
+
+    (&entities, &equipped, &names)
+        .join()
+        .filter(|(_entity, equipped_by, _name)| equipped_by.owner == *player_entity)
+        .map(|(entity, equipped_by, name)| (entity, equipped_by.slot, name.name.to_string()))
+        .collect()
+}
+
+/// Lists the player's gear slot by slot, next to the inventory.
+fn draw_equipment(ecs: &World, ctx: &mut Rltk) {
+    let equipment = player_equipment(ecs);
+    let slots = [
+        (EquipmentSlot::Melee, "Melee"),
+        (EquipmentSlot::Shield, "Shield"),
+        (EquipmentSlot::Armor, "Armor"),
+        (EquipmentSlot::Head, "Head"),
+        (EquipmentSlot::Ring, "Ring"),
+    ];
+
+    let y = 25 - (slots.len() as i32 / 2);
+    ctx.draw_box(
+        48,
+        y - 2,
+        30,
+        slots.len() as i32 + 3,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        51,
+        y - 2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Equipped",
+    );
+
+    for (i, (slot, label)) in slots.iter().enumerate() {
+        let y = y + i as i32;
+        let item = equipment
+            .iter()
+            .find(|(_entity, equipped_slot, _name)| equipped_slot == slot)
+            .map(|(_entity, _slot, name)| name.as_str())
+            .unwrap_or("-");
+        ctx.print_color(
+            50,
+            y,
+            RGB::named(rltk::GREY),
+            RGB::named(rltk::BLACK),
+            &format!("{}:", label),
+        );
+        ctx.print(58, y, item);
+    }
+}
+
+pub fn show_inventory(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
+    let items = player_backpack(&gs.ecs);
+    draw_equipment(&gs.ecs, ctx);
+    item_menu(ctx, "Inventory - use which item?", &items)
+}
+
+pub fn remove_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
+    let items: Vec<(Entity, String)> = player_equipment(&gs.ecs)
+        .into_iter()
+        .map(|(entity, _slot, name)| (entity, name))
+        .collect();
+    item_menu(ctx, "Remove which item?", &items)
+}
+
+pub fn drop_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
+    let items = player_backpack(&gs.ecs);
+    item_menu(ctx, "Drop which item?", &items)
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum TargetingResult {
+    Cancel,
+    NoResponse { cursor: Point },
+    Selected { target: Point },
+}
+
+/// Movement of the keyboard targeting cursor, using the same keys as player movement.
+fn cursor_delta(key: VirtualKeyCode) -> Option<(i32, i32)> {
+    match key {
+        VirtualKeyCode::Left | VirtualKeyCode::Numpad4 | VirtualKeyCode::H => Some((-1, 0)),
+        VirtualKeyCode::Right | VirtualKeyCode::Numpad6 | VirtualKeyCode::L => Some((1, 0)),
+        VirtualKeyCode::Up | VirtualKeyCode::Numpad8 | VirtualKeyCode::K => Some((0, -1)),
+        VirtualKeyCode::Down | VirtualKeyCode::Numpad2 | VirtualKeyCode::J => Some((0, 1)),
+        VirtualKeyCode::Numpad9 | VirtualKeyCode::Y => Some((1, -1)),
+        VirtualKeyCode::Numpad7 | VirtualKeyCode::U => Some((-1, -1)),
+        VirtualKeyCode::Numpad3 | VirtualKeyCode::N => Some((1, 1)),
+        VirtualKeyCode::Numpad1 | VirtualKeyCode::B => Some((-1, 1)),
+        _ => None,
+    }
+}
+
+/// Highlights the visible tiles within `range` of the player and lets them pick one,
+/// either by clicking it or by moving `cursor` onto it and pressing Enter.
+pub fn ranged_target(gs: &mut State, ctx: &mut Rltk, range: i32, cursor: Point) -> TargetingResult {
+    let player_entity = gs.ecs.fetch::<Entity>();
+    let player_pos = gs.ecs.fetch::<Point>();
+    let viewsheds = gs.ecs.read_storage::<Viewshed>();
+
+    ctx.print_color(
+        5,
+        0,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Select Target (mouse, or move and press Enter). ESCAPE to cancel",
+    );
+
+    // Highlight available target cells
+    let mut available_cells = Vec::new();
+    match viewsheds.get(*player_entity) {
+        None => return TargetingResult::Cancel,
+        Some(visible) => {
+            for tile in visible.visible_tiles.iter() {
+                let distance = rltk::DistanceAlg::Pythagoras.distance2d(*player_pos, *tile);
+                if distance <= range as f32 {
+                    if let Some(screen) = camera::world_to_screen(&gs.ecs, *tile) {
+                        ctx.set_bg(screen.x, screen.y, RGB::named(rltk::BLUE));
+                    }
+                    available_cells.push(*tile);
+                }
+            }
+        }
+    }
+
+    // Draw the keyboard cursor
+    let cursor_valid = available_cells.contains(&cursor);
+    if let Some(screen) = camera::world_to_screen(&gs.ecs, cursor) {
+        let color = if cursor_valid { rltk::CYAN } else { rltk::RED };
+        ctx.set_bg(screen.x, screen.y, RGB::named(color));
+    }
+
+    // Draw the mouse cursor; clicking outside the available cells cancels
+    let mouse_pos = ctx.mouse_pos();
+    let mouse = camera::screen_to_world(&gs.ecs, mouse_pos.0, mouse_pos.1);
+    if available_cells.contains(&mouse) {
+        ctx.set_bg(mouse_pos.0, mouse_pos.1, RGB::named(rltk::CYAN));
+        if ctx.left_click {
+            return TargetingResult::Selected { target: mouse };
+        }
+    } else {
+        ctx.set_bg(mouse_pos.0, mouse_pos.1, RGB::named(rltk::RED));
+        if ctx.left_click {
+            return TargetingResult::Cancel;
+        }
+    }
+
+    match ctx.key {
+        None => TargetingResult::NoResponse { cursor },
+        Some(key) => match key {
+            VirtualKeyCode::Escape => TargetingResult::Cancel,
+            VirtualKeyCode::Return if cursor_valid => TargetingResult::Selected { target: cursor },
+            _ => match cursor_delta(key) {
+                Some((delta_x, delta_y)) => {
+                    // Keep the cursor on the map and within range of the player
+                    let map = gs.ecs.fetch::<Map>();
+                    let x = (cursor.x + delta_x).clamp(
+                        i32::max(0, player_pos.x - range),
+                        i32::min(map.width - 1, player_pos.x + range),
+                    );
+                    let y = (cursor.y + delta_y).clamp(
+                        i32::max(0, player_pos.y - range),
+                        i32::min(map.height - 1, player_pos.y + range),
+                    );
+                    TargetingResult::NoResponse {
+                        cursor: Point::new(x, y),
+                    }
+                }
+                None => TargetingResult::NoResponse { cursor },
+            },
+        },
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum MainMenuSelection {
+    NewGame,
+    LoadGame,
+    Quit,
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum MainMenuResult {
+    NoSelection { selected: MainMenuSelection },
+    Selected { selected: MainMenuSelection },
+}
+
+pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
+    let save_exists = saveload_system::does_save_exist(saveload_system::SAVE_PATH);
+    let runstate = gs.ecs.fetch::<RunState>();
+
+    ctx.print_color_centered(
+        15,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Rusty Roguelike",
+    );
+
+    if let RunState::MainMenu {
+        menu_selection: selection,
+    } = *runstate
+    {
+        // Build the list of available entries, skipping `Continue` if there's nothing to load
+        let mut entries = vec![(MainMenuSelection::NewGame, "Begin New Game")];
+        if save_exists {
+            entries.push((MainMenuSelection::LoadGame, "Continue"));
+        }
+        entries.push((MainMenuSelection::Quit, "Quit"));
+
+        for (i, (entry, label)) in entries.iter().enumerate() {
+            let y = 24 + i as i32;
+            let fg = if *entry == selection {
+                RGB::named(rltk::MAGENTA)
+            } else {
+                RGB::named(rltk::WHITE)
+            };
+            ctx.print_color_centered(y, fg, RGB::named(rltk::BLACK), label);
+        }
+
+        // Index of the current selection, so up/down wrap around the visible entries
+        let current = entries
+            .iter()
+            .position(|(entry, _)| *entry == selection)
+            .unwrap_or(0);
+
+        return match ctx.key {
+            None => MainMenuResult::NoSelection {
+                selected: entries[current].0,
+            },
+            Some(key) => match key {
+                VirtualKeyCode::Escape => MainMenuResult::NoSelection {
+                    selected: MainMenuSelection::Quit,
+                },
+                VirtualKeyCode::Up | VirtualKeyCode::K => MainMenuResult::NoSelection {
+                    selected: entries[(current + entries.len() - 1) % entries.len()].0,
+                },
+                VirtualKeyCode::Down | VirtualKeyCode::J => MainMenuResult::NoSelection {
+                    selected: entries[(current + 1) % entries.len()].0,
+                },
+                VirtualKeyCode::Return => MainMenuResult::Selected {
+                    selected: entries[current].0,
+                },
+                _ => MainMenuResult::NoSelection {
+                    selected: entries[current].0,
+                },
+            },
+        };
+    }
+
+    MainMenuResult::NoSelection {
+        selected: MainMenuSelection::NewGame,
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum GameOverResult {
+    NoSelection,
+    QuitToMenu,
+}
+
+pub fn game_over(ecs: &World, ctx: &mut Rltk) -> GameOverResult {
+    let stats = ecs.fetch::<RunStats>();
+
+    ctx.print_color_centered(
+        15,
+        RGB::named(rltk::RED),
+        RGB::named(rltk::BLACK),
+        "Your journey has ended!",
+    );
+
+    let cause = match &stats.last_hit_by {
+        Some(killer) => format!("You were killed by {}", killer),
+        None => "You died".to_string(),
+    };
+    ctx.print_color_centered(18, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), &cause);
+    ctx.print_color_centered(
+        20,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+        &format!("You survived for {} turns", stats.turns),
+    );
+    ctx.print_color_centered(
+        21,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+        &format!("You killed {} monsters", stats.kills),
+    );
+
+    ctx.print_color_centered(
+        24,
+        RGB::named(rltk::MAGENTA),
+        RGB::named(rltk::BLACK),
+        "Press any key to return to the menu.",
+    );
+
+    match ctx.key {
+        None => GameOverResult::NoSelection,
+        Some(_) => GameOverResult::QuitToMenu,
+    }
+}
@@ -0,0 +1,280 @@
+use super::{
+    damage_system, player, run_stats::RunStats, CombatStats, Item, Map, Monster, RunState, State,
+    TileType,
+};
+use rltk::{Point, RandomNumberGenerator};
+use specs::prelude::*;
+
+/// Something the player can do on their turn without a console.
+#[derive(Copy, Clone)]
+pub enum PlayerAction {
+    Move { delta_x: i32, delta_y: i32 },
+    PickUp,
+    Descend,
+}
+
+impl PlayerAction {
+    /// Parses one of the game's movement/action keys: `hjklyubn`, `g` or `.`.
+    pub fn from_key(key: char) -> Option<PlayerAction> {
+        let (delta_x, delta_y) = match key {
+            'h' => (-1, 0),
+            'l' => (1, 0),
+            'k' => (0, -1),
+            'j' => (0, 1),
+            'y' => (1, -1),
+            'u' => (-1, -1),
+            'n' => (1, 1),
+            'b' => (-1, 1),
+            'g' => return Some(PlayerAction::PickUp),
+            '.' => return Some(PlayerAction::Descend),
+            _ => return None,
+        };
+        Some(PlayerAction::Move { delta_x, delta_y })
+    }
+
+    /// Does the action. Anything that can't be done (like picking up from an empty
+    /// tile) still uses the turn, so a policy can never stall the simulation.
+    fn perform(self, ecs: &mut World) -> RunState {
+        let result = match self {
+            PlayerAction::Move { delta_x, delta_y } => {
+                player::try_move_player(delta_x, delta_y, ecs);
+                RunState::PlayerTurn
+            }
+            PlayerAction::PickUp => player::get_item(ecs),
+            PlayerAction::Descend => player::try_next_level(ecs),
+        };
+
+        match result {
+            RunState::AwaitingInput => RunState::PlayerTurn,
+            _ => result,
+        }
+    }
+}
+
+/// Decides what the player does each turn of a headless run.
+pub trait Policy {
+    fn next_action(&mut self, ecs: &World) -> PlayerAction;
+}
+
+/// Wanders at random, but fights anything next to it, picks up whatever it
+/// stands on and takes the stairs whenever it finds them.
+pub struct RandomPolicy {
+    rng: RandomNumberGenerator,
+}
+
+impl RandomPolicy {
+    pub fn new(seed: u64) -> RandomPolicy {
+        RandomPolicy {
+            rng: RandomNumberGenerator::seeded(seed),
+        }
+    }
+}
+
+const DIRECTIONS: [(i32, i32); 8] = [
+    (-1, 0),
+    (1, 0),
+    (0, -1),
+    (0, 1),
+    (-1, -1),
+    (1, -1),
+    (-1, 1),
+    (1, 1),
+];
+
+impl Policy for RandomPolicy {
+    fn next_action(&mut self, ecs: &World) -> PlayerAction {
+        let player_pos = ecs.fetch::<Point>();
+        let map = ecs.fetch::<Map>();
+        let monsters = ecs.read_storage::<Monster>();
+        let combat_stats = ecs.read_storage::<CombatStats>();
+        let items = ecs.read_storage::<Item>();
+
+        for (delta_x, delta_y) in DIRECTIONS.iter() {
+            let idx = map.xy_idx(player_pos.x + delta_x, player_pos.y + delta_y);
+            let has_enemy = map.tile_content[idx]
+                .iter()
+                .any(|e| monsters.get(*e).is_some() && combat_stats.get(*e).is_some());
+            if has_enemy {
+                return PlayerAction::Move {
+                    delta_x: *delta_x,
+                    delta_y: *delta_y,
+                };
+            }
+        }
+
+        let idx = map.xy_idx(player_pos.x, player_pos.y);
+        if map.tiles[idx] == TileType::DownStairs {
+            return PlayerAction::Descend;
+        }
+        if map.tile_content[idx]
+            .iter()
+            .any(|e| items.get(*e).is_some())
+        {
+            return PlayerAction::PickUp;
+        }
+
+        let pick = (self.rng.roll_dice(1, DIRECTIONS.len() as i32) - 1) as usize;
+        let (delta_x, delta_y) = DIRECTIONS[pick];
+        PlayerAction::Move { delta_x, delta_y }
+    }
+}
+
+/// Plays a fixed list of actions, starting over when it runs out.
+pub struct ScriptedPolicy {
+    actions: Vec<PlayerAction>,
+    next: usize,
+}
+
+impl ScriptedPolicy {
+    /// Builds the script from a string of action keys, see `PlayerAction::from_key`.
+    pub fn from_keys(keys: &str) -> Result<ScriptedPolicy, String> {
+        let actions = keys
+            .chars()
+            .map(|key| {
+                PlayerAction::from_key(key).ok_or_else(|| format!("Unknown action key '{}'", key))
+            })
+            .collect::<Result<Vec<PlayerAction>, String>>()?;
+
+        if actions.is_empty() {
+            return Err("The script has no actions".to_string());
+        }
+
+        Ok(ScriptedPolicy { actions, next: 0 })
+    }
+}
+
+impl Policy for ScriptedPolicy {
+    fn next_action(&mut self, _ecs: &World) -> PlayerAction {
+        let action = self.actions[self.next];
+        self.next = (self.next + 1) % self.actions.len();
+        action
+    }
+}
+
+/// How a single headless run went.
+pub struct SimulationReport {
+    pub seed: u64,
+    pub died: bool,
+    pub turns: i32,
+    pub damage_dealt: i32,
+    pub kills: i32,
+    pub depth: i32,
+}
+
+/// Plays one game from `seed` with no console, until the player dies or
+/// `max_turns` turns have gone by.
+pub fn simulate(seed: u64, max_turns: i32, policy: &mut dyn Policy) -> SimulationReport {
+    let mut gs = State::new(false);
+    gs.new_game(seed);
+
+    // The systems read the state from the world, as they do under `tick`
+    let mut runstate = RunState::PreRun;
+    gs.ecs.insert(runstate);
+    while runstate != RunState::GameOver && gs.ecs.fetch::<RunStats>().turns < max_turns {
+        runstate = match runstate {
+            RunState::AwaitingInput => policy.next_action(&gs.ecs).perform(&mut gs.ecs),
+            _ => gs.step(runstate),
+        };
+
+        // Same bookkeeping as the end of `tick`; a death switches to GameOver here
+        gs.ecs.insert(runstate);
+        damage_system::delete_the_dead(&mut gs.ecs);
+        runstate = *gs.ecs.fetch::<RunState>();
+    }
+
+    let stats = (*gs.ecs.fetch::<RunStats>()).clone();
+    let depth = gs.ecs.fetch::<Map>().depth;
+    let player_alive = {
+        let player_entity = gs.ecs.fetch::<Entity>();
+        let combat_stats = gs.ecs.read_storage::<CombatStats>();
+        combat_stats
+            .get(*player_entity)
+            .is_some_and(|player_stats| player_stats.hp > 0)
+    };
+
+    SimulationReport {
+        seed,
+        died: !player_alive,
+        turns: stats.turns,
+        damage_dealt: stats.damage_dealt,
+        kills: stats.kills,
+        depth,
+    }
+}
+
+/// Settings for a batch of headless runs, read from the command line.
+pub struct HeadlessOptions {
+    pub runs: u64,
+    pub max_turns: i32,
+    pub script: Option<String>,
+}
+
+/// `--headless [--runs N] [--turns N] [--script KEYS]`, or `None` to play normally.
+pub fn requested_options() -> Option<HeadlessOptions> {
+    let args: Vec<String> = std::env::args().collect();
+    if !args.iter().any(|arg| arg == "--headless") {
+        return None;
+    }
+
+    let value_of = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|i| args.get(i + 1))
+            .cloned()
+    };
+
+    Some(HeadlessOptions {
+        runs: value_of("--runs")
+            .and_then(|runs| runs.parse::<u64>().ok())
+            .unwrap_or(1),
+        max_turns: value_of("--turns")
+            .and_then(|turns| turns.parse::<i32>().ok())
+            .unwrap_or(1000),
+        script: value_of("--script"),
+    })
+}
+
+/// Plays `options.runs` games with consecutive seeds from `first_seed`, printing
+/// a line per run and a summary at the end.
+pub fn run_batch(options: &HeadlessOptions, first_seed: u64) -> Result<(), String> {
+    let mut deaths = 0;
+    let mut total_turns: i64 = 0;
+    let mut total_damage: i64 = 0;
+
+    for run in 0..options.runs {
+        let seed = first_seed.wrapping_add(run);
+        let mut policy: Box<dyn Policy> = match &options.script {
+            Some(keys) => Box::new(ScriptedPolicy::from_keys(keys)?),
+            None => Box::new(RandomPolicy::new(seed)),
+        };
+
+        let report = simulate(seed, options.max_turns, policy.as_mut());
+        println!(
+            "run {}: seed {}, {}, {} turns, {} damage dealt, {} kills, depth {}",
+            run,
+            report.seed,
+            if report.died { "died" } else { "survived" },
+            report.turns,
+            report.damage_dealt,
+            report.kills,
+            report.depth
+        );
+
+        if report.died {
+            deaths += 1;
+        }
+        total_turns += i64::from(report.turns);
+        total_damage += i64::from(report.damage_dealt);
+    }
+
+    let runs = i64::max(1, options.runs as i64);
+    println!(
+        "{} runs: {} deaths, {} turns survived and {} damage dealt on average",
+        options.runs,
+        deaths,
+        total_turns / runs,
+        total_damage / runs
+    );
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use super::{
+    gamelog::GameLog, run_stats::RunStats, HungerClock, HungerState, MyTurn, SufferDamage,
+};
+use specs::prelude::*;
+
+/// Turns spent in each state before dropping to the next one.
+pub const HUNGER_STATE_DURATION: i32 = 200;
+
+/// Hp lost every turn while starving.
+const STARVATION_DAMAGE: i32 = 1;
+
+pub struct HungerSystem {}
+
+impl<'a> System<'a> for HungerSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Entity>, // Player entity resource
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, RunStats>,
+        Entities<'a>,
+        WriteStorage<'a, HungerClock>,
+        ReadStorage<'a, MyTurn>,
+        WriteStorage<'a, SufferDamage>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            player_entity,
+            mut log,
+            mut run_stats,
+            entities,
+            mut hunger_clocks,
+            turns,
+            mut inflict_damage,
+        ) = data;
+
+        // The clock only moves when its owner takes a turn
+        for (entity, clock, _turn) in (&entities, &mut hunger_clocks, &turns).join() {
+            clock.duration -= 1;
+            if clock.duration > 0 {
+                continue;
+            }
+
+            let is_player = entity == *player_entity;
+            match clock.state {
+                HungerState::WellFed => {
+                    clock.state = HungerState::Normal;
+                    clock.duration = HUNGER_STATE_DURATION;
+                    if is_player {
+                        log.entries
+                            .insert(0, "You are no longer well fed.".to_string());
+                    }
+                }
+                HungerState::Normal => {
+                    clock.state = HungerState::Hungry;
+                    clock.duration = HUNGER_STATE_DURATION;
+                    if is_player {
+                        log.entries.insert(0, "You are hungry.".to_string());
+                    }
+                }
+                HungerState::Hungry => {
+                    clock.state = HungerState::Starving;
+                    clock.duration = HUNGER_STATE_DURATION;
+                    if is_player {
+                        log.entries.insert(0, "You are starving!".to_string());
+                    }
+                }
+                HungerState::Starving => {
+                    // Stays starving, and hurts every turn until something is eaten
+                    clock.duration = 0;
+                    SufferDamage::new_damage(&mut inflict_damage, entity, STARVATION_DAMAGE, None);
+                    if is_player {
+                        run_stats.last_hit_by = Some("starvation".to_string());
+                        log.entries.insert(
+                            0,
+                            format!(
+                                "Your hunger pangs are getting painful! You suffer {} hp damage.",
+                                STARVATION_DAMAGE
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
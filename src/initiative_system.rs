@@ -0,0 +1,50 @@
+use super::{Energy, MyTurn, RunState};
+use specs::prelude::*;
+
+/// Energy an entity spends to take one action.
+pub const ACTION_COST: i32 = 100;
+
+/// Energy per tick of the player and of anything without a speed in the raws.
+/// Half an action, so something twice as fast acts every tick.
+pub const NORMAL_SPEED: i32 = 50;
+
+pub struct InitiativeSystem {}
+
+impl<'a> System<'a> for InitiativeSystem {
+    type SystemData = (
+        WriteExpect<'a, RunState>,
+        ReadExpect<'a, Entity>, // Player entity resource
+        Entities<'a>,
+        WriteStorage<'a, Energy>,
+        WriteStorage<'a, MyTurn>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut runstate, player_entity, entities, mut energies, mut turns) = data;
+
+        // Whoever had a turn last tick has taken it by now. Cleared on every run, so
+        // systems that act on a turn never see a stale one outside of a tick.
+        turns.clear();
+
+        // Only hand out turns while the clock is ticking
+        if *runstate != RunState::Ticking {
+            return;
+        }
+
+        for (entity, energy) in (&entities, &mut energies).join() {
+            energy.energy += energy.speed;
+            if energy.energy >= ACTION_COST {
+                // At most one action per tick, so anything faster than that is wasted
+                energy.energy = i32::min(energy.energy - ACTION_COST, ACTION_COST - 1);
+                turns
+                    .insert(entity, MyTurn {})
+                    .expect("@ERROR: Unable to insert turn");
+
+                // Stop the clock for the player to decide what to do
+                if entity == *player_entity {
+                    *runstate = RunState::AwaitingInput;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use super::{
+    gamelog::GameLog, hunger_system::HUNGER_STATE_DURATION, name_of, run_stats::RunStats,
+    AreaOfEffect, CombatStats, Confusion, Consumable, Equippable, Equipped, HungerClock,
+    HungerState, InBackpack, InflictsDamage, Map, Name, Position, ProvidesFood, ProvidesHealing,
+    SufferDamage, WantsToDropItem, WantsToPickupItem, WantsToRemoveItem, WantsToUseItem,
+};
+use specs::prelude::*;
+
+pub struct ItemCollectionSystem {}
+
+impl<'a> System<'a> for ItemCollectionSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Entity>, // Player entity resource
+        WriteExpect<'a, GameLog>,
+        WriteStorage<'a, WantsToPickupItem>,
+        WriteStorage<'a, Position>,
+        ReadStorage<'a, Name>,
+        WriteStorage<'a, InBackpack>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (player_entity, mut log, mut wants_pickup, mut positions, names, mut backpack) = data;
+
+        for pickup in wants_pickup.join() {
+            // Off the map and into the collector's backpack
+            positions.remove(pickup.item);
+            backpack
+                .insert(
+                    pickup.item,
+                    InBackpack {
+                        owner: pickup.collected_by,
+                    },
+                )
+                .expect("@ERROR: Unable to insert item in backpack");
+
+            if pickup.collected_by == *player_entity {
+                log.entries.insert(
+                    0,
+                    format!("You pick up the {}.", name_of(&names, pickup.item)),
+                );
+            }
+        }
+
+        // Remove the wants_pickup `message` component
+        wants_pickup.clear();
+    }
+}
+
+pub struct ItemUseSystem {}
+
+impl<'a> System<'a> for ItemUseSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Entity>, // Player entity resource
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, RunStats>,
+        ReadExpect<'a, Map>,
+        Entities<'a>,
+        WriteStorage<'a, WantsToUseItem>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Consumable>,
+        ReadStorage<'a, ProvidesHealing>,
+        ReadStorage<'a, InflictsDamage>,
+        ReadStorage<'a, AreaOfEffect>,
+        WriteStorage<'a, Confusion>,
+        WriteStorage<'a, CombatStats>,
+        WriteStorage<'a, SufferDamage>,
+        ReadStorage<'a, Equippable>,
+        WriteStorage<'a, Equipped>,
+        WriteStorage<'a, InBackpack>,
+        ReadStorage<'a, ProvidesFood>,
+        WriteStorage<'a, HungerClock>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            player_entity,
+            mut log,
+            mut run_stats,
+            map,
+            entities,
+            mut wants_use,
+            names,
+            consumables,
+            healing,
+            inflict_damage,
+            aoe,
+            mut confused,
+            mut combat_stats,
+            mut suffer_damage,
+            equippable,
+            mut equipped,
+            mut backpack,
+            provides_food,
+            mut hunger_clocks,
+        ) = data;
+
+        for (entity, use_item) in (&entities, &wants_use).join() {
+            let item_name = name_of(&names, use_item.item);
+
+            // Work out who the item affects. Only creatures (things with combat stats) count.
+            let mut targets: Vec<Entity> = Vec::new();
+            match use_item.target {
+                None => targets.push(entity),
+                Some(target) => {
+                    let tiles = match aoe.get(use_item.item) {
+                        // Single target
+                        None => vec![target],
+                        // Everything in the blast radius that the blast can reach
+                        Some(area) => {
+                            let mut blast_tiles = rltk::field_of_view(target, area.radius, &*map);
+                            blast_tiles.retain(|p| {
+                                p.x > 0 && p.x < map.width - 1 && p.y > 0 && p.y < map.height - 1
+                            });
+                            blast_tiles
+                        }
+                    };
+
+                    for tile in tiles.iter() {
+                        let idx = map.xy_idx(tile.x, tile.y);
+                        for creature in map.tile_content[idx].iter() {
+                            if combat_stats.get(*creature).is_some() {
+                                targets.push(*creature);
+                            }
+                        }
+                    }
+                }
+            }
+
+            // Equippable items go into their slot, swapping out whatever was there.
+            // Only a creature can wear one, so there's nothing to do without a target.
+            if let (Some(can_equip), Some(&target)) =
+                (equippable.get(use_item.item), targets.first())
+            {
+                let target_slot = can_equip.slot;
+
+                let mut to_unequip: Vec<Entity> = Vec::new();
+                for (item_entity, already_equipped) in (&entities, &equipped).join() {
+                    if already_equipped.owner == target && already_equipped.slot == target_slot {
+                        to_unequip.push(item_entity);
+                        if target == *player_entity {
+                            log.entries.insert(
+                                0,
+                                format!("You unequip the {}.", name_of(&names, item_entity)),
+                            );
+                        }
+                    }
+                }
+                for item in to_unequip.iter() {
+                    equipped.remove(*item);
+                    backpack
+                        .insert(*item, InBackpack { owner: target })
+                        .expect("@ERROR: Unable to insert unequipped item in backpack");
+                }
+
+                equipped
+                    .insert(
+                        use_item.item,
+                        Equipped {
+                            owner: target,
+                            slot: target_slot,
+                        },
+                    )
+                    .expect("@ERROR: Unable to equip item");
+                backpack.remove(use_item.item);
+                if target == *player_entity {
+                    log.entries
+                        .insert(0, format!("You equip the {}.", item_name));
+                }
+            }
+
+            // Healing items top up hp, never past the maximum
+            if let Some(healer) = healing.get(use_item.item) {
+                for target in targets.iter() {
+                    if let Some(stats) = combat_stats.get_mut(*target) {
+                        let hp_before = stats.hp;
+                        stats.hp = i32::min(stats.max_hp, stats.hp + healer.heal_amount);
+                        if entity == *player_entity {
+                            log.entries.insert(
+                                0,
+                                format!(
+                                    "You use the {}, healing {} hp.",
+                                    item_name,
+                                    stats.hp - hp_before
+                                ),
+                            );
+                        }
+                    }
+                }
+            }
+
+            // Food fills the eater right up
+            if provides_food.get(use_item.item).is_some() {
+                for target in targets.iter() {
+                    if let Some(clock) = hunger_clocks.get_mut(*target) {
+                        clock.state = HungerState::WellFed;
+                        clock.duration = HUNGER_STATE_DURATION;
+                        if *target == *player_entity {
+                            log.entries.insert(0, format!("You eat the {}.", item_name));
+                        }
+                    }
+                }
+            }
+
+            // Damaging items hurt every target
+            if let Some(damage) = inflict_damage.get(use_item.item) {
+                for target in targets.iter() {
+                    SufferDamage::new_damage(
+                        &mut suffer_damage,
+                        *target,
+                        damage.damage,
+                        Some(entity),
+                    );
+                    if *target == *player_entity {
+                        run_stats.last_hit_by = Some(item_name.clone());
+                    }
+                    if entity == *player_entity {
+                        run_stats.damage_dealt += damage.damage;
+                        let target_name = name_of(&names, *target);
+                        log.entries.insert(
+                            0,
+                            format!(
+                                "You use {} on {}, inflicting {} hp.",
+                                item_name, target_name, damage.damage
+                            ),
+                        );
+                    }
+                }
+            }
+
+            // Confusing items leave every target confused for a while
+            let confusion_turns = confused.get(use_item.item).map(|c| c.turns);
+            if let Some(turns) = confusion_turns {
+                for target in targets.iter() {
+                    confused
+                        .insert(*target, Confusion { turns })
+                        .expect("@ERROR: Unable to insert confusion");
+                    if entity == *player_entity {
+                        let target_name = name_of(&names, *target);
+                        log.entries.insert(
+                            0,
+                            format!("You use {} on {}, confusing them.", item_name, target_name),
+                        );
+                    }
+                }
+            }
+
+            // Consumables are used up
+            if consumables.get(use_item.item).is_some() {
+                entities
+                    .delete(use_item.item)
+                    .expect("@ERROR: Unable to delete consumed item");
+            }
+        }
+
+        // Remove the wants_use `message` component
+        wants_use.clear();
+    }
+}
+
+pub struct ItemDropSystem {}
+
+impl<'a> System<'a> for ItemDropSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Entity>, // Player entity resource
+        WriteExpect<'a, GameLog>,
+        Entities<'a>,
+        WriteStorage<'a, WantsToDropItem>,
+        ReadStorage<'a, Name>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, InBackpack>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (player_entity, mut log, entities, mut wants_drop, names, mut positions, mut backpack) =
+            data;
+
+        for (entity, to_drop) in (&entities, &wants_drop).join() {
+            // The item lands at the dropper's feet
+            let dropper_pos = {
+                let pos = positions.get(entity).unwrap();
+                Position { x: pos.x, y: pos.y }
+            };
+            positions
+                .insert(to_drop.item, dropper_pos)
+                .expect("@ERROR: Unable to insert dropped item position");
+            backpack.remove(to_drop.item);
+
+            if entity == *player_entity {
+                log.entries.insert(
+                    0,
+                    format!("You drop the {}.", name_of(&names, to_drop.item)),
+                );
+            }
+        }
+
+        // Remove the wants_drop `message` component
+        wants_drop.clear();
+    }
+}
+
+pub struct ItemRemoveSystem {}
+
+impl<'a> System<'a> for ItemRemoveSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToRemoveItem>,
+        WriteStorage<'a, Equipped>,
+        WriteStorage<'a, InBackpack>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut wants_remove, mut equipped, mut backpack) = data;
+
+        // Unequipped items go back into the owner's backpack
+        for (entity, to_remove) in (&entities, &wants_remove).join() {
+            equipped.remove(to_remove.item);
+            backpack
+                .insert(to_remove.item, InBackpack { owner: entity })
+                .expect("@ERROR: Unable to insert unequipped item in backpack");
+        }
+
+        // Remove the wants_remove `message` component
+        wants_remove.clear();
+    }
+}
@@ -0,0 +1,773 @@
+use rltk::{Console, GameState, Point, RandomNumberGenerator, Rltk};
+use specs::prelude::*;
+use specs::saveload::{SimpleMarker, SimpleMarkerAllocator};
+#[macro_use]
+extern crate specs_derive;
+
+// Our modules
+pub mod components;
+pub use components::*;
+
+pub mod map;
+pub use map::*;
+
+pub mod player;
+use player::*;
+
+pub mod rect;
+pub use rect::Rect;
+
+pub mod visibility_system;
+use visibility_system::VisibilitySystem;
+
+pub mod initiative_system;
+use initiative_system::InitiativeSystem;
+
+pub mod hunger_system;
+use hunger_system::HungerSystem;
+
+pub mod monster_ai_system;
+use monster_ai_system::MonsterAI;
+
+pub mod door_system;
+use door_system::DoorSystem;
+
+pub mod trigger_system;
+use trigger_system::TriggerSystem;
+
+pub mod perception_system;
+use perception_system::PerceptionSystem;
+
+pub mod map_indexing_system;
+use map_indexing_system::MapIndexingSystem;
+
+pub mod melee_combat_system;
+use melee_combat_system::MeleeCombatSystem;
+
+pub mod damage_system;
+use damage_system::DamageSystem;
+
+pub mod inventory_system;
+use inventory_system::{ItemCollectionSystem, ItemDropSystem, ItemRemoveSystem, ItemUseSystem};
+
+pub mod map_builders;
+use map_builders::MapBuilder;
+
+pub mod autopilot;
+pub mod camera;
+pub mod gamelog;
+pub mod gui;
+pub mod headless;
+pub mod random_table;
+pub mod raws;
+use raws::RawMaster;
+pub mod run_stats;
+pub mod saveload_system;
+pub mod spawner;
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum RunState {
+    AwaitingInput,
+    PreRun,
+    // The clock runs until it's the player's turn; see `InitiativeSystem`
+    Ticking,
+    PlayerTurn,
+    SaveGame,
+    ShowInventory,
+    ShowDropItem,
+    ShowRemoveItem,
+    NextLevel,
+    ShowTargeting {
+        range: i32,
+        item: Entity,
+        cursor: Point,
+    },
+    MainMenu {
+        menu_selection: gui::MainMenuSelection,
+    },
+    GameOver,
+    MapGeneration,
+}
+
+/// How long each map generation snapshot stays on screen.
+const MAPGEN_FRAME_MS: f32 = 200.0;
+
+/// How close an ally the player can't see has to be to follow them downstairs.
+const FOLLOW_DOWN_RADIUS: f32 = 3.0;
+
+pub struct State {
+    pub ecs: World,
+    // Debug replay of how the current level was generated; see `RunState::MapGeneration`
+    show_mapgen: bool,
+    mapgen_next_state: Option<RunState>,
+    mapgen_history: Vec<Map>,
+    mapgen_index: usize,
+    mapgen_timer: f32,
+}
+
+/// Registers every component with `ecs` (internally creates storage systems, etc).
+pub fn register_components(ecs: &mut World) {
+    ecs.register::<Position>();
+    ecs.register::<Renderable>();
+    ecs.register::<Player>();
+    ecs.register::<Viewshed>();
+    ecs.register::<Monster>();
+    ecs.register::<Name>();
+    ecs.register::<BlocksTile>();
+    ecs.register::<CombatStats>();
+    ecs.register::<WantsToMelee>();
+    ecs.register::<SufferDamage>();
+    ecs.register::<Item>();
+    ecs.register::<InBackpack>();
+    ecs.register::<WantsToPickupItem>();
+    ecs.register::<Consumable>();
+    ecs.register::<ProvidesHealing>();
+    ecs.register::<WantsToUseItem>();
+    ecs.register::<WantsToDropItem>();
+    ecs.register::<Ranged>();
+    ecs.register::<InflictsDamage>();
+    ecs.register::<AreaOfEffect>();
+    ecs.register::<Confusion>();
+    ecs.register::<Equippable>();
+    ecs.register::<Equipped>();
+    ecs.register::<MeleePowerBonus>();
+    ecs.register::<DefenseBonus>();
+    ecs.register::<WantsToRemoveItem>();
+    ecs.register::<Energy>();
+    ecs.register::<MyTurn>();
+    ecs.register::<HungerClock>();
+    ecs.register::<ProvidesFood>();
+    ecs.register::<Experience>();
+    ecs.register::<XpValue>();
+    ecs.register::<Door>();
+    ecs.register::<BlocksVisibility>();
+    ecs.register::<WantsToOpenDoor>();
+    ecs.register::<Hidden>();
+    ecs.register::<EntryTrigger>();
+    ecs.register::<SingleActivation>();
+    ecs.register::<TeleportsTarget>();
+    ecs.register::<SoundsAlarm>();
+    ecs.register::<AiBehaviour>();
+    ecs.register::<MonsterAiState>();
+    ecs.register::<Faction>();
+    ecs.register::<Provoked>();
+    ecs.register::<EntityMoved>();
+    ecs.register::<SimpleMarker<SerializeMe>>();
+    ecs.register::<SerializationHelper>();
+}
+
+/// Runs every game system once, in order. Both the game loop and the tests
+/// step the world through this.
+pub fn run_systems(ecs: &mut World) {
+    // Run Initiative system
+    let mut initiative = InitiativeSystem {};
+    initiative.run_now(ecs);
+
+    // Index the map before anyone looks at it too, so a fresh level's doors
+    // already block sight on the first pass
+    let mut map_index = MapIndexingSystem {};
+    map_index.run_now(ecs);
+
+    // Run Visibility System
+    let mut vis = VisibilitySystem {};
+    vis.run_now(ecs);
+
+    // Run Perception system
+    let mut perception = PerceptionSystem {};
+    perception.run_now(ecs);
+
+    // Run Monster AI System
+    let mut mob = MonsterAI {};
+    mob.run_now(ecs);
+
+    // Run Door system
+    let mut doors = DoorSystem {};
+    doors.run_now(ecs);
+
+    // Run Trigger system
+    let mut triggers = TriggerSystem {};
+    triggers.run_now(ecs);
+
+    // Run Map Indexing system again, now everyone has moved
+    map_index.run_now(ecs);
+
+    // Run Melee Combat system
+    let mut melee = MeleeCombatSystem {};
+    melee.run_now(ecs);
+
+    // Run Hunger system
+    let mut hunger = HungerSystem {};
+    hunger.run_now(ecs);
+
+    // Run Damage system
+    let mut damage = DamageSystem {};
+    damage.run_now(ecs);
+
+    // Run Item Collection system
+    let mut pickup = ItemCollectionSystem {};
+    pickup.run_now(ecs);
+
+    // Run Item Use system
+    let mut use_items = ItemUseSystem {};
+    use_items.run_now(ecs);
+
+    // Run Item Drop system
+    let mut drop_items = ItemDropSystem {};
+    drop_items.run_now(ecs);
+
+    // Run Item Remove system
+    let mut remove_items = ItemRemoveSystem {};
+    remove_items.run_now(ecs);
+
+    ecs.maintain();
+}
+
+impl State {
+    /// An empty world with every component registered and the raws loaded,
+    /// ready for `new_game`. Needs no console, so it can also be run headless.
+    pub fn new(show_mapgen: bool) -> State {
+        let mut gs = State {
+            ecs: World::new(),
+            show_mapgen,
+            mapgen_next_state: None,
+            mapgen_history: Vec::new(),
+            mapgen_index: 0,
+            mapgen_timer: 0.0,
+        };
+
+        register_components(&mut gs.ecs);
+
+        gs.ecs.insert(autopilot::Autopilot::Off);
+
+        // Hands out the ids that entity references are saved with
+        gs.ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+
+        // Monster & item definitions, used by the spawner
+        match raws::load_raws() {
+            Ok(raws) => gs.ecs.insert(raws),
+            Err(e) => panic!("@ERROR: {}", e),
+        }
+
+        gs
+    }
+
+    /// Throws away whatever is in the world and builds a fresh, unplayed game from `seed`.
+    pub fn new_game(&mut self, seed: u64) {
+        // Delete every entity left over from the previous run
+        let to_delete: Vec<Entity> = self.ecs.entities().join().collect();
+        for del in to_delete {
+            self.ecs
+                .delete_entity(del)
+                .expect("@ERROR: Unable to delete entity");
+        }
+
+        // Every random roll in the game comes from this one seeded generator
+        self.ecs.insert(RandomNumberGenerator::seeded(seed));
+
+        // Create a new map
+        let mut builder = self.build_level(1);
+        self.record_mapgen_history(builder.as_ref());
+        let map = builder.get_map();
+
+        // Put the player where the builder wants them before moving map into the ECS world
+        let start = builder.get_starting_position();
+        let (player_x, player_y) = (start.x, start.y);
+        let player_entity = spawner::player(&mut self.ecs, player_x, player_y);
+
+        builder.spawn_entities(&mut self.ecs);
+
+        self.ecs.insert(map); // The map is now available from everywhere the ECS can see!
+        self.ecs.insert(Point::new(player_x, player_y)); // Add player position as an ECS resource (updated in player input)
+        self.ecs.insert(player_entity);
+        self.ecs.insert(run_stats::RunStats::default());
+        self.ecs.insert(autopilot::Autopilot::Off);
+        self.ecs.insert(gamelog::GameLog {
+            entries: vec![
+                "Welcome to Rusty Roguelike".to_string(),
+                format!("Dungeon seed: {}", seed),
+            ],
+        });
+    }
+
+    /// Generates the map for `depth` with whichever builder the raws pick for it.
+    /// Nothing is spawned yet; that is left to the caller.
+    fn build_level(&mut self, depth: i32) -> Box<dyn MapBuilder> {
+        let raws = self.ecs.fetch::<RawMaster>();
+        let mut rng = self.ecs.write_resource::<RandomNumberGenerator>();
+        let mut builder = map_builders::level_builder(depth, self.show_mapgen, &mut rng, &raws);
+        builder.build_map(&mut rng);
+        builder
+    }
+
+    /// Keeps the builder's snapshots for replaying. There are none unless the visualizer is on.
+    fn record_mapgen_history(&mut self, builder: &dyn MapBuilder) {
+        self.mapgen_history = builder.get_snapshot_history();
+        self.mapgen_index = 0;
+        self.mapgen_timer = 0.0;
+    }
+
+    /// The state to move to once a new level is ready: a replay of how it was
+    /// generated if there is one to show, then `next_state`.
+    fn after_map_generation(&mut self, next_state: RunState) -> RunState {
+        if self.mapgen_history.is_empty() {
+            return next_state;
+        }
+        self.mapgen_next_state = Some(next_state);
+        RunState::MapGeneration
+    }
+
+    /// Everything except the player, what they carry or wear, and the allies following them.
+    fn entities_to_remove_on_level_change(&mut self, allies: &[Entity]) -> Vec<Entity> {
+        let entities = self.ecs.entities();
+        let player = self.ecs.read_storage::<Player>();
+        let backpack = self.ecs.read_storage::<InBackpack>();
+        let equipped = self.ecs.read_storage::<Equipped>();
+        let player_entity = self.ecs.fetch::<Entity>();
+
+        let mut to_delete: Vec<Entity> = Vec::new();
+        for entity in entities.join() {
+            let mut should_delete = true;
+
+            // Don't delete the player, or whoever follows them down
+            if player.get(entity).is_some() || allies.contains(&entity) {
+                should_delete = false;
+            }
+
+            // Don't delete the player's stuff
+            if let Some(bp) = backpack.get(entity) {
+                if bp.owner == *player_entity {
+                    should_delete = false;
+                }
+            }
+            if let Some(eq) = equipped.get(entity) {
+                if eq.owner == *player_entity {
+                    should_delete = false;
+                }
+            }
+
+            if should_delete {
+                to_delete.push(entity);
+            }
+        }
+
+        to_delete
+    }
+
+    /// Creatures on the player's side that are close enough to follow them down:
+    /// in sight of the player, or just around a corner.
+    fn followers(&self) -> Vec<Entity> {
+        let entities = self.ecs.entities();
+        let player = self.ecs.read_storage::<Player>();
+        let factions = self.ecs.read_storage::<Faction>();
+        let positions = self.ecs.read_storage::<Position>();
+        let viewsheds = self.ecs.read_storage::<Viewshed>();
+        let player_entity = self.ecs.fetch::<Entity>();
+        let player_pos = *self.ecs.fetch::<Point>();
+        let in_sight = viewsheds
+            .get(*player_entity)
+            .map_or(&[][..], |viewshed| &viewshed.visible_tiles[..]);
+
+        (&entities, &factions, &positions, !&player)
+            .join()
+            .filter(|(_entity, faction, pos, _player)| {
+                let pos = Point::new(pos.x, pos.y);
+                faction.name == spawner::PLAYER_FACTION
+                    && (in_sight.contains(&pos)
+                        || rltk::DistanceAlg::Pythagoras.distance2d(player_pos, pos)
+                            <= FOLLOW_DOWN_RADIUS)
+            })
+            .map(|(entity, _faction, _pos, _player)| entity)
+            .collect()
+    }
+
+    /// Puts `allies` on the free floor nearest the player's new start, with their
+    /// memories of the old level wiped. Any there's no room for stay behind.
+    fn place_allies(&mut self, start: Point, allies: Vec<Entity>) {
+        let mut left_behind: Vec<Entity> = Vec::new();
+        {
+            let map = self.ecs.fetch::<Map>();
+            let entities = self.ecs.entities();
+            let blockers = self.ecs.read_storage::<BlocksTile>();
+            let doors = self.ecs.read_storage::<Door>();
+            let mut positions = self.ecs.write_storage::<Position>();
+            let mut viewsheds = self.ecs.write_storage::<Viewshed>();
+            let mut ai_states = self.ecs.write_storage::<MonsterAiState>();
+            let mut provoked = self.ecs.write_storage::<Provoked>();
+
+            // Whatever was spawned on the new level already stands somewhere, and the
+            // player doesn't block tiles but stands on the start
+            let start_idx = map.xy_idx(start.x, start.y);
+            let mut occupied: Vec<usize> = (&entities, &positions, &blockers)
+                .join()
+                .filter(|(entity, _pos, _blocks)| !allies.contains(entity))
+                .map(|(_entity, pos, _blocks)| map.xy_idx(pos.x, pos.y))
+                .collect();
+            occupied.push(start_idx);
+            // Doors don't block tiles, but nobody should end up stuck inside a closed one
+            occupied.extend(
+                (&positions, &doors)
+                    .join()
+                    .filter(|(_pos, door)| !door.open)
+                    .map(|(pos, _door)| map.xy_idx(pos.x, pos.y)),
+            );
+
+            let dijkstra = rltk::DijkstraMap::new(
+                map.width,
+                map.height,
+                &[start_idx as i32],
+                &*map,
+                map.dijkstra_max_depth(),
+            );
+            let mut free: Vec<usize> = (0..map.tiles.len())
+                .filter(|idx| {
+                    map.tiles[*idx] == TileType::Floor
+                        && dijkstra.map[*idx] < f32::MAX
+                        && !occupied.contains(idx)
+                })
+                .collect();
+            free.sort_by(|a, b| dijkstra.map[*a].partial_cmp(&dijkstra.map[*b]).unwrap());
+
+            let mut free = free.into_iter();
+            for ally in allies {
+                let idx = match free.next() {
+                    Some(idx) => idx,
+                    None => {
+                        left_behind.push(ally);
+                        continue;
+                    }
+                };
+                if let Some(pos) = positions.get_mut(ally) {
+                    pos.x = idx as i32 % map.width;
+                    pos.y = idx as i32 / map.width;
+                }
+                if let Some(viewshed) = viewsheds.get_mut(ally) {
+                    viewshed.dirty = true;
+                }
+                if let Some(ai) = ai_states.get_mut(ally) {
+                    ai.state = AiState::Idle;
+                }
+                provoked.remove(ally);
+            }
+        }
+
+        for ally in left_behind {
+            self.ecs
+                .delete_entity(ally)
+                .expect("@ERROR: Unable to delete entity");
+        }
+    }
+
+    /// Builds the next level down and moves the player (and their gear and nearby allies) onto it.
+    pub fn goto_next_level(&mut self) {
+        // Delete entities that aren't the player, their equipment or their followers
+        let followers = self.followers();
+        let to_delete = self.entities_to_remove_on_level_change(&followers);
+        for target in to_delete {
+            self.ecs
+                .delete_entity(target)
+                .expect("@ERROR: Unable to delete entity");
+        }
+
+        // Build a new map and populate it
+        let current_depth = self.ecs.fetch::<Map>().depth;
+        let mut builder = self.build_level(current_depth + 1);
+        self.record_mapgen_history(builder.as_ref());
+        builder.spawn_entities(&mut self.ecs);
+
+        // Place the player at the builder's start and update the resources
+        let start = builder.get_starting_position();
+        let (player_x, player_y) = (start.x, start.y);
+        self.ecs.insert(builder.get_map());
+        self.ecs.insert(Point::new(player_x, player_y));
+
+        let player_entity = *self.ecs.fetch::<Entity>();
+        {
+            let mut positions = self.ecs.write_storage::<Position>();
+            if let Some(player_pos) = positions.get_mut(player_entity) {
+                player_pos.x = player_x;
+                player_pos.y = player_y;
+            }
+        }
+
+        self.place_allies(Point::new(player_x, player_y), followers);
+
+        // We moved so recalculate the player's visibility
+        {
+            let mut viewsheds = self.ecs.write_storage::<Viewshed>();
+            if let Some(viewshed) = viewsheds.get_mut(player_entity) {
+                viewshed.dirty = true;
+            }
+        }
+
+        // Notify the player and give them some health
+        let mut log = self.ecs.write_resource::<gamelog::GameLog>();
+        log.entries.insert(
+            0,
+            "You descend to the next level, and take a moment to heal.".to_string(),
+        );
+        let mut combat_stats = self.ecs.write_storage::<CombatStats>();
+        if let Some(player_health) = combat_stats.get_mut(player_entity) {
+            player_health.hp = i32::max(player_health.hp, player_health.max_hp / 2);
+        }
+    }
+
+    /// Continues the game saved at `path`. The save is used up by loading it,
+    /// so a character who dies can't be brought back by loading it again.
+    pub fn load_game(&mut self, path: &str) -> Result<(), String> {
+        saveload_system::load_game(&mut self.ecs, path)?;
+        if let Err(e) = saveload_system::delete_save(path) {
+            // The game loaded fine, so play on; the player just gets to keep the save
+            let mut log = self.ecs.write_resource::<gamelog::GameLog>();
+            log.entries
+                .insert(0, format!("Unable to delete saved game: {}", e));
+        }
+        Ok(())
+    }
+
+    /// Advances the states that play out without any input from the player.
+    fn step(&mut self, runstate: RunState) -> RunState {
+        match runstate {
+            RunState::PreRun => {
+                run_systems(&mut self.ecs);
+                RunState::Ticking
+            }
+            RunState::Ticking => {
+                // One tick at a time; the initiative system stops the clock on the player's turn
+                run_systems(&mut self.ecs);
+                *self.ecs.fetch::<RunState>()
+            }
+            RunState::PlayerTurn => {
+                run_systems(&mut self.ecs);
+                self.ecs.write_resource::<run_stats::RunStats>().turns += 1;
+                RunState::Ticking
+            }
+            RunState::NextLevel => {
+                self.goto_next_level();
+                self.after_map_generation(RunState::PreRun)
+            }
+            _ => runstate,
+        }
+    }
+
+    /// Draws the seed for the next run from the current one, so a whole session replays from one seed.
+    fn next_seed(&mut self) -> u64 {
+        let mut rng = self.ecs.write_resource::<RandomNumberGenerator>();
+        rng.rand::<u64>()
+    }
+}
+
+impl GameState for State {
+    fn tick(&mut self, ctx: &mut Rltk) {
+        // Clear console
+        ctx.cls();
+
+        let mut new_runstate;
+        // Read the resource into a new var
+        {
+            let runstate = self.ecs.fetch::<RunState>();
+            new_runstate = *runstate;
+        }
+
+        // Menus draw over an empty screen, everything else over the map
+        match new_runstate {
+            RunState::MainMenu { .. } | RunState::GameOver | RunState::MapGeneration => {}
+            _ => {
+                // The map and everything on it, around the player
+                camera::render_camera(&self.ecs, ctx);
+
+                // And finally draw our gui
+                gui::draw_ui(&self.ecs, ctx);
+            }
+        }
+
+        match new_runstate {
+            RunState::MapGeneration => {
+                if self.mapgen_index < self.mapgen_history.len() {
+                    camera::render_debug_map(&self.mapgen_history[self.mapgen_index], ctx);
+
+                    self.mapgen_timer += ctx.frame_time_ms;
+                    if self.mapgen_timer > MAPGEN_FRAME_MS {
+                        self.mapgen_timer = 0.0;
+                        self.mapgen_index += 1;
+                    }
+                } else {
+                    // Played out, so the replay isn't shown again for this level
+                    self.mapgen_history.clear();
+                    new_runstate = self.mapgen_next_state.unwrap_or(RunState::PreRun);
+                }
+            }
+            RunState::PreRun | RunState::Ticking | RunState::PlayerTurn | RunState::NextLevel => {
+                new_runstate = self.step(new_runstate);
+            }
+            RunState::AwaitingInput => {
+                // If player makes a move returns a state other than AwaitingInput
+                new_runstate = player_input(self, ctx);
+            }
+            RunState::SaveGame => {
+                match saveload_system::save_game(&mut self.ecs, saveload_system::SAVE_PATH) {
+                    Ok(()) => {
+                        // Leave a fresh world behind, so `Begin New Game` doesn't resume the saved one
+                        let seed = self.next_seed();
+                        self.new_game(seed);
+                        new_runstate = RunState::MainMenu {
+                            menu_selection: gui::MainMenuSelection::LoadGame,
+                        };
+                    }
+                    Err(e) => {
+                        // Nothing was lost, so tell the player and let them keep playing
+                        let mut log = self.ecs.write_resource::<gamelog::GameLog>();
+                        log.entries.insert(0, format!("Unable to save game: {}", e));
+                        new_runstate = RunState::AwaitingInput;
+                    }
+                }
+            }
+            RunState::ShowInventory => {
+                let result = gui::show_inventory(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => new_runstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let item_entity = result.1.unwrap();
+                        let is_ranged = self.ecs.read_storage::<Ranged>();
+                        match is_ranged.get(item_entity) {
+                            // Ranged items need a target first; the cursor starts on the player
+                            Some(ranged) => {
+                                new_runstate = RunState::ShowTargeting {
+                                    range: ranged.range,
+                                    item: item_entity,
+                                    cursor: *self.ecs.fetch::<Point>(),
+                                };
+                            }
+                            None => {
+                                let mut intent = self.ecs.write_storage::<WantsToUseItem>();
+                                intent
+                                    .insert(
+                                        *self.ecs.fetch::<Entity>(),
+                                        WantsToUseItem {
+                                            item: item_entity,
+                                            target: None,
+                                        },
+                                    )
+                                    .expect("@ERROR: Unable to insert intent to use item");
+                                new_runstate = RunState::PlayerTurn;
+                            }
+                        }
+                    }
+                }
+            }
+            RunState::ShowTargeting {
+                range,
+                item,
+                cursor,
+            } => {
+                let result = gui::ranged_target(self, ctx, range, cursor);
+                match result {
+                    gui::TargetingResult::Cancel => new_runstate = RunState::AwaitingInput,
+                    gui::TargetingResult::NoResponse { cursor } => {
+                        new_runstate = RunState::ShowTargeting {
+                            range,
+                            item,
+                            cursor,
+                        }
+                    }
+                    gui::TargetingResult::Selected { target } => {
+                        let mut intent = self.ecs.write_storage::<WantsToUseItem>();
+                        intent
+                            .insert(
+                                *self.ecs.fetch::<Entity>(),
+                                WantsToUseItem {
+                                    item,
+                                    target: Some(target),
+                                },
+                            )
+                            .expect("@ERROR: Unable to insert intent to use item");
+                        new_runstate = RunState::PlayerTurn;
+                    }
+                }
+            }
+            RunState::ShowRemoveItem => {
+                let result = gui::remove_item_menu(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => new_runstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let item_entity = result.1.unwrap();
+                        let mut intent = self.ecs.write_storage::<WantsToRemoveItem>();
+                        intent
+                            .insert(
+                                *self.ecs.fetch::<Entity>(),
+                                WantsToRemoveItem { item: item_entity },
+                            )
+                            .expect("@ERROR: Unable to insert intent to remove item");
+                        new_runstate = RunState::PlayerTurn;
+                    }
+                }
+            }
+            RunState::ShowDropItem => {
+                let result = gui::drop_item_menu(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => new_runstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let item_entity = result.1.unwrap();
+                        let mut intent = self.ecs.write_storage::<WantsToDropItem>();
+                        intent
+                            .insert(
+                                *self.ecs.fetch::<Entity>(),
+                                WantsToDropItem { item: item_entity },
+                            )
+                            .expect("@ERROR: Unable to insert intent to drop item");
+                        new_runstate = RunState::PlayerTurn;
+                    }
+                }
+            }
+            RunState::MainMenu { .. } => {
+                let result = gui::main_menu(self, ctx);
+                match result {
+                    gui::MainMenuResult::NoSelection { selected } => {
+                        new_runstate = RunState::MainMenu {
+                            menu_selection: selected,
+                        }
+                    }
+                    gui::MainMenuResult::Selected { selected } => match selected {
+                        gui::MainMenuSelection::NewGame => {
+                            new_runstate = self.after_map_generation(RunState::PreRun)
+                        }
+                        gui::MainMenuSelection::LoadGame => {
+                            // On failure the fresh world is kept, and the player told why
+                            if let Err(e) = self.load_game(saveload_system::SAVE_PATH) {
+                                let mut log = self.ecs.write_resource::<gamelog::GameLog>();
+                                log.entries
+                                    .insert(0, format!("Unable to load saved game: {}", e));
+                            }
+                            new_runstate = RunState::PreRun;
+                        }
+                        gui::MainMenuSelection::Quit => ::std::process::exit(0),
+                    },
+                }
+            }
+            RunState::GameOver => {
+                let result = gui::game_over(&self.ecs, ctx);
+                match result {
+                    gui::GameOverResult::NoSelection => {}
+                    gui::GameOverResult::QuitToMenu => {
+                        let seed = self.next_seed();
+                        self.new_game(seed);
+                        new_runstate = RunState::MainMenu {
+                            menu_selection: gui::MainMenuSelection::NewGame,
+                        };
+                    }
+                }
+            }
+        }
+
+        // Write the updated run state into the resource
+        {
+            let mut run_writer = self.ecs.write_resource::<RunState>();
+            *run_writer = new_runstate;
+        }
+
+        // After systems run, delete any dead entities
+        damage_system::delete_the_dead(&mut self.ecs);
+    }
+}
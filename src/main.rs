@@ -1,247 +1,57 @@
-use rltk::{Console, GameState, Point, Rltk, RGB};
-use specs::prelude::*;
-#[macro_use]
-extern crate specs_derive;
+use rltk::{RandomNumberGenerator, Rltk};
+use rogue_like::{gui, headless, RunState, State};
 
 rltk::add_wasm_support!();
 
-// Our modules
-mod components;
-pub use components::*;
-
-mod map;
-pub use map::*;
-
-mod player;
-use player::*;
-
-mod rect;
-pub use rect::Rect;
-
-mod visibility_system;
-use visibility_system::VisibilitySystem;
-
-mod monster_ai_system;
-use monster_ai_system::MonsterAI;
-
-mod map_indexing_system;
-use map_indexing_system::MapIndexingSystem;
-
-mod melee_combat_system;
-use melee_combat_system::MeleeCombatSystem;
-
-mod damage_system;
-use damage_system::DamageSystem;
-
-mod gamelog;
-mod gui;
-
-#[derive(PartialEq, Copy, Clone)]
-pub enum RunState {
-    AwaitingInput,
-    PreRun,
-    PlayerTurn,
-    MonsterTurn,
+/// Debug flag: replay each level's generation before playing it.
+/// Turned on with `--mapgen` or the `ROGUE_MAPGEN` environment variable.
+fn mapgen_visualizer_requested() -> bool {
+    std::env::args().any(|arg| arg == "--mapgen") || std::env::var("ROGUE_MAPGEN").is_ok()
 }
 
-pub struct State {
-    ecs: World,
+/// Returns the dungeon seed requested on the command line (`--seed <n>`)
+/// or through the `ROGUE_SEED` environment variable, in that order.
+fn requested_seed() -> Option<u64> {
+    let args: Vec<String> = std::env::args().collect();
+    let from_args = args
+        .iter()
+        .position(|arg| arg == "--seed")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|seed| seed.parse::<u64>().ok());
+
+    from_args.or_else(|| {
+        std::env::var("ROGUE_SEED")
+            .ok()
+            .and_then(|seed| seed.parse::<u64>().ok())
+    })
 }
 
-impl State {
-    fn run_systems(&mut self) {
-        // Run Visibility System
-        let mut vis = VisibilitySystem {};
-        vis.run_now(&self.ecs);
-
-        // Run Monster AI System
-        let mut mob = MonsterAI {};
-        mob.run_now(&self.ecs);
-
-        // Run Map Indexing system
-        let mut map_index = MapIndexingSystem {};
-        map_index.run_now(&self.ecs);
-
-        // Run Melee Combat system
-        let mut melee = MeleeCombatSystem {};
-        melee.run_now(&self.ecs);
-
-        // Run Damage system
-        let mut damage = DamageSystem {};
-        damage.run_now(&self.ecs);
-
-        self.ecs.maintain();
-    }
-}
-
-impl GameState for State {
-    fn tick(&mut self, ctx: &mut Rltk) {
-        // Clear console
-        ctx.cls();
-
-        let mut new_runstate;
-        // Read the resource into a new var
-        {
-            let runstate = self.ecs.fetch::<RunState>();
-            new_runstate = *runstate;
-        }
-
-        match new_runstate {
-            RunState::PreRun => {
-                self.run_systems();
-                new_runstate = RunState::AwaitingInput;
-            }
-            RunState::AwaitingInput => {
-                // If player makes a move returns a state other than AwaitingInput
-                new_runstate = player_input(self, ctx);
-            }
-            RunState::PlayerTurn => {
-                self.run_systems();
-                new_runstate = RunState::MonsterTurn;
-            }
-            RunState::MonsterTurn => {
-                self.run_systems();
-                new_runstate = RunState::AwaitingInput;
-            }
-        }
-
-        // Write the updated run state into the resource
-        {
-            let mut run_writer = self.ecs.write_resource::<RunState>();
-            *run_writer = new_runstate;
-        }
-
-        // After systems run, delete any dead entities
-        damage_system::delete_the_dead(&mut self.ecs);
-
-        // The map is a resource, so get it from ecs world
-        draw_map(&self.ecs, ctx);
-
-        // Draw other entities (player, monster, etc)
-        let positions = self.ecs.read_storage::<Position>();
-        let renderables = self.ecs.read_storage::<Renderable>();
-        let map = self.ecs.fetch::<Map>();
-
-        for (pos, render) in (&positions, &renderables).join() {
-            let idx = map.xy_idx(pos.x, pos.y);
-            if map.visible_tiles[idx] {
-                ctx.set(pos.x, pos.y, render.fg, render.bg, render.glyph);
-            }
+fn main() {
+    // If no seed was requested we pick one, so that it can still be reported and replayed.
+    let seed = requested_seed().unwrap_or_else(|| RandomNumberGenerator::new().rand::<u64>());
+
+    // Automated play for balance testing; never opens a window
+    if let Some(options) = headless::requested_options() {
+        if let Err(e) = headless::run_batch(&options, seed) {
+            eprintln!("@ERROR: {}", e);
+            std::process::exit(1);
         }
-
-        // And finally draw our gui
-        gui::draw_ui(&self.ecs, ctx);
+        return;
     }
-}
 
-fn main() {
     let mut ctx = Rltk::init_simple8x8(80, 50, "Hello Rust World", "resources");
 
     // Add post-processing
     ctx.with_post_scanlines(true);
 
     // Create our gamestate with an ecs world in it.
-    let mut gs = State { ecs: World::new() };
-
-    // Register our components with the ecs world (internally creates storage systems, etc)
-    gs.ecs.register::<Position>();
-    gs.ecs.register::<Renderable>();
-    gs.ecs.register::<Player>();
-    gs.ecs.register::<Viewshed>();
-    gs.ecs.register::<Monster>();
-    gs.ecs.register::<Name>();
-    gs.ecs.register::<BlocksTile>();
-    gs.ecs.register::<CombatStats>();
-    gs.ecs.register::<WantsToMelee>();
-    gs.ecs.register::<SufferDamage>();
-
-    // Create a new map
-    let map: Map = Map::new_map_rooms_and_corridors();
-    // Put the player in the center of the 1st room before moving map into the ECS world
-    let (player_x, player_y) = map.rooms[0].center();
-
-    // Create a player entity
-    let player_entity = gs
-        .ecs
-        .create_entity()
-        .with(Position {
-            x: player_x,
-            y: player_y,
-        })
-        .with(Renderable {
-            glyph: rltk::to_cp437('@'),
-            fg: RGB::named(rltk::YELLOW),
-            bg: RGB::named(rltk::BLACK),
-        })
-        .with(Player {})
-        .with(Viewshed {
-            visible_tiles: Vec::new(),
-            range: 8,
-            dirty: true,
-        })
-        .with(Name {
-            name: "Player".to_string(),
-        })
-        .with(CombatStats {
-            max_hp: 30,
-            hp: 30,
-            defense: 2,
-            power: 5,
-        })
-        .build();
+    let mut gs = State::new(mapgen_visualizer_requested());
 
-    // Create monsters
-    let mut rng = rltk::RandomNumberGenerator::new();
-    for (i, room) in map.rooms.iter().skip(1).enumerate() {
-        let (x, y) = room.center();
-
-        let glyph: u8;
-        let name: String;
-        let roll = rng.roll_dice(1, 2);
-        match roll {
-            1 => {
-                glyph = rltk::to_cp437('g');
-                name = "Goblin".to_string();
-            }
-            _ => {
-                glyph = rltk::to_cp437('o');
-                name = "Orc".to_string();
-            }
-        }
-
-        gs.ecs
-            .create_entity()
-            .with(Position { x, y })
-            .with(Renderable {
-                glyph: glyph,
-                fg: RGB::named(rltk::RED),
-                bg: RGB::named(rltk::BLACK),
-            })
-            .with(Viewshed {
-                visible_tiles: Vec::new(),
-                range: 8,
-                dirty: true,
-            })
-            .with(Monster {})
-            .with(Name {
-                name: format!("{} #{}", &name, i),
-            })
-            .with(BlocksTile {})
-            .with(CombatStats {
-                max_hp: 16,
-                hp: 16,
-                defense: 1,
-                power: 4,
-            })
-            .build();
-    }
+    println!("Dungeon seed: {}", seed);
+    gs.new_game(seed);
 
-    gs.ecs.insert(map); // The map is now available from everywhere the ECS can see!
-    gs.ecs.insert(Point::new(player_x, player_y)); // Add player position as an ECS resource (updated in player input)
-    gs.ecs.insert(player_entity);
-    gs.ecs.insert(RunState::PreRun);
-    gs.ecs.insert(gamelog::GameLog {
-        entries: vec!["Welcome to Rusty Roguelike".to_string()],
+    gs.ecs.insert(RunState::MainMenu {
+        menu_selection: gui::MainMenuSelection::NewGame,
     });
 
     rltk::main_loop(ctx, gs);
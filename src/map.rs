@@ -1,23 +1,24 @@
 use super::Rect;
-use rltk::{Algorithm2D, BaseMap, Console, Point, Rltk, RGB};
+use rltk::{Algorithm2D, BaseMap, Point};
+use serde::{Deserialize, Serialize};
 use specs::prelude::*;
-use std::cmp::{max, min};
 
-const MAPWIDTH: usize = 80;
-const MAPHEIGHT: usize = 43;
-const MAPCOUNT: usize = MAPHEIGHT * MAPWIDTH;
+/// Size of a level that doesn't ask for anything else; exactly fills the view.
+pub const DEFAULT_MAP_WIDTH: i32 = 80;
+pub const DEFAULT_MAP_HEIGHT: i32 = 43;
 
 // Copy & Clone allow this enum to be used
 // as a `value` type, that is, passed around by value
 // instead of pointers
 // PartialEq allow us to use == to see if two tile types match
-#[derive(PartialEq, Copy, Clone)]
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum TileType {
     Wall,
     Floor,
+    DownStairs,
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize, Clone)]
 pub struct Map {
     pub tiles: Vec<TileType>,
     pub rooms: Vec<Rect>,
@@ -26,6 +27,17 @@ pub struct Map {
     pub revealed_tiles: Vec<bool>,
     pub visible_tiles: Vec<bool>,
     pub blocked: Vec<bool>,
+    pub depth: i32,
+
+    // Tiles sight can't pass besides walls, like closed doors. Rebuilt by the
+    // map indexing system, so it isn't saved either
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    pub view_blocked: Vec<bool>,
+
+    // Rebuilt every turn by the map indexing system, so it never goes into a save
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
     pub tile_content: Vec<Vec<Entity>>,
 }
 
@@ -34,33 +46,6 @@ impl Map {
         (y as usize * self.width as usize) + x as usize
     }
 
-    fn apply_room_to_map(&mut self, room: &Rect) {
-        for y in room.y1 + 1..=room.y2 {
-            for x in room.x1 + 1..=room.x2 {
-                let idx = self.xy_idx(x, y);
-                self.tiles[idx] = TileType::Floor;
-            }
-        }
-    }
-
-    fn apply_horizontal_tunnel(&mut self, x1: i32, x2: i32, y: i32) {
-        for x in min(x1, x2)..=max(x1, x2) {
-            let idx = self.xy_idx(x, y);
-            if idx > 0 && idx < self.width as usize * self.height as usize {
-                self.tiles[idx as usize] = TileType::Floor;
-            }
-        }
-    }
-
-    fn apply_vertical_tunnel(&mut self, y1: i32, y2: i32, x: i32) {
-        for y in min(y1, y2)..=max(y1, y2) {
-            let idx = self.xy_idx(x, y);
-            if idx > 0 && idx < self.width as usize * self.height as usize {
-                self.tiles[idx as usize] = TileType::Floor;
-            }
-        }
-    }
-
     fn is_exit_valid(&self, x: i32, y: i32) -> bool {
         if x < 1 || x > self.width - 1 || y < 1 || y > self.height - 1 {
             return false;
@@ -81,69 +66,74 @@ impl Map {
         }
     }
 
-    /// Makes a new map using the algorithm from http://rogueliketutorials.com/tutorials/tcod/part-3/
-    /// This gives a handful of random rooms and corridors joining them together.
-    pub fn new_map_rooms_and_corridors() -> Map {
-        let mut map = Map {
-            tiles: vec![TileType::Wall; MAPCOUNT],
-            rooms: Vec::new(),
-            width: MAPWIDTH as i32,
-            height: MAPHEIGHT as i32,
-            revealed_tiles: vec![false; MAPCOUNT],
-            visible_tiles: vec![false; MAPCOUNT],
-            blocked: vec![false; MAPCOUNT],
-            tile_content: vec![Vec::new(); MAPCOUNT],
-        };
-
-        const MAX_ROOMS: i32 = 30;
-        const MIN_SIZE: i32 = 6;
-        const MAX_SIZE: i32 = 10;
-
-        let mut rng = rltk::RandomNumberGenerator::new();
-
-        for _i in 0..MAX_ROOMS {
-            let w = rng.range(MIN_SIZE, MAX_SIZE);
-            let h = rng.range(MIN_SIZE, MAX_SIZE);
-
-            let x = rng.roll_dice(1, map.width - w - 1) - 1;
-            let y = rng.roll_dice(1, map.height - h - 1) - 1;
-
-            let new_room = Rect::new(x, y, w, h);
+    /// How far a Dijkstra flood may spread. Even a walk through every tile, all
+    /// of it diagonal, costs less, so nothing reachable is cut off however big the map.
+    pub fn dijkstra_max_depth(&self) -> f32 {
+        (self.width * self.height) as f32 * 1.45
+    }
 
-            let mut ok = true;
-            for other_room in map.rooms.iter() {
-                if new_room.intersect(other_room) {
-                    ok = false
-                }
+    /// Post-processing for a freshly built map. Floods out from the player's start
+    /// tile, walls over any floor that can't be walked to from there, and puts the
+    /// stairs down on the reachable tile furthest (by walking distance) from it.
+    pub fn cull_unreachable_and_place_exit(&mut self, start_x: i32, start_y: i32) {
+        // Any stairs left by the builder are moved, and walls must block the flood
+        for tile in self.tiles.iter_mut() {
+            if *tile == TileType::DownStairs {
+                *tile = TileType::Floor;
             }
-
-            if ok {
-                map.apply_room_to_map(&new_room);
-
-                if !map.rooms.is_empty() {
-                    let (new_x, new_y) = new_room.center();
-                    let (prev_x, prev_y) = map.rooms[map.rooms.len() - 1].center();
-
-                    if rng.range(0, 1) == 1 {
-                        map.apply_horizontal_tunnel(prev_x, new_x, prev_y);
-                        map.apply_vertical_tunnel(prev_y, new_y, new_x);
-                    } else {
-                        map.apply_vertical_tunnel(prev_y, new_y, prev_x);
-                        map.apply_horizontal_tunnel(prev_x, new_x, new_y);
-                    }
+        }
+        self.populate_blocked();
+
+        let start_idx = self.xy_idx(start_x, start_y);
+        let dijkstra_map = rltk::DijkstraMap::new(
+            self.width,
+            self.height,
+            &[start_idx as i32],
+            &*self,
+            self.dijkstra_max_depth(),
+        );
+
+        let mut exit_idx = start_idx;
+        let mut exit_distance = 0.0f32;
+        for (idx, tile) in self.tiles.iter_mut().enumerate() {
+            if *tile == TileType::Floor {
+                let distance = dijkstra_map.map[idx];
+                if distance == f32::MAX {
+                    // Nobody can get here, so it may as well be rock
+                    *tile = TileType::Wall;
+                } else if distance > exit_distance {
+                    exit_distance = distance;
+                    exit_idx = idx;
                 }
-
-                map.rooms.push(new_room);
             }
         }
 
-        map
+        self.tiles[exit_idx] = TileType::DownStairs;
+        self.populate_blocked();
+    }
+
+    /// Makes a new map of solid wall, ready for a map builder to carve into.
+    pub fn new(new_depth: i32, width: i32, height: i32) -> Map {
+        let map_tile_count = (width * height) as usize;
+        Map {
+            tiles: vec![TileType::Wall; map_tile_count],
+            rooms: Vec::new(),
+            width,
+            height,
+            revealed_tiles: vec![false; map_tile_count],
+            visible_tiles: vec![false; map_tile_count],
+            blocked: vec![false; map_tile_count],
+            depth: new_depth,
+            view_blocked: vec![false; map_tile_count],
+            tile_content: vec![Vec::new(); map_tile_count],
+        }
     }
 }
 
 impl BaseMap for Map {
     fn is_opaque(&self, idx: i32) -> bool {
-        self.tiles[idx as usize] == TileType::Wall
+        let idx = idx as usize;
+        self.tiles[idx] == TileType::Wall || self.view_blocked[idx]
     }
 
     fn get_available_exits(&self, idx: i32) -> Vec<(i32, f32)> {
@@ -176,7 +166,7 @@ impl BaseMap for Map {
             exits.push(((idx + self.width) - 1, 1.45));
         }
         if self.is_exit_valid(x + 1, y + 1) {
-            exits.push(((idx - self.width) - 1, 1.45));
+            exits.push(((idx + self.width) + 1, 1.45));
         }
 
         exits
@@ -201,43 +191,3 @@ impl Algorithm2D for Map {
         }
     }
 }
-
-pub fn draw_map(ecs: &World, ctx: &mut Rltk) {
-    let map = ecs.fetch::<Map>();
-
-    let mut x = 0;
-    let mut y = 0;
-
-    for (idx, tile) in map.tiles.iter().enumerate() {
-        // Render a tile depending on it's type
-
-        if map.revealed_tiles[idx] {
-            let glyph;
-            let mut fg;
-
-            match tile {
-                TileType::Floor => {
-                    glyph = rltk::to_cp437('.');
-                    fg = RGB::from_f32(0.0, 0.5, 0.5);
-                }
-                TileType::Wall => {
-                    glyph = rltk::to_cp437('#');
-                    fg = RGB::from_f32(0., 1.0, 0.);
-                }
-            }
-
-            if !map.visible_tiles[idx] {
-                fg = fg.to_greyscale()
-            }
-
-            ctx.set(x, y, fg, RGB::from_f32(0., 0., 0.), glyph);
-        }
-
-        // Move the coordinates
-        x += 1;
-        if x > MAPWIDTH as i32 - 1 {
-            x = 0;
-            y += 1;
-        }
-    } // @LOOP END
-}
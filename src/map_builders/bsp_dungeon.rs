@@ -0,0 +1,193 @@
+use super::{
+    apply_room_to_map, door_positions, first_room_center, spawner, BuilderMap, MapBuilder,
+    Position, Rect, TileType,
+};
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+
+/// Rooms placed by binary space partition: the map is split into ever smaller
+/// rectangles, a room is placed inside some of them and the rooms are joined
+/// left to right with corridors.
+pub struct BspDungeonBuilder {
+    data: BuilderMap,
+    rects: Vec<Rect>,
+}
+
+impl MapBuilder for BspDungeonBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.build(rng);
+    }
+
+    fn spawn_entities(&mut self, ecs: &mut World) {
+        for (x, y) in door_positions(&self.data.map) {
+            spawner::door(ecs, x, y);
+        }
+
+        // The first room is where the player starts, so it is left empty
+        for room in self.data.map.rooms.iter().skip(1) {
+            spawner::spawn_room(ecs, &self.data.map, room);
+        }
+    }
+
+    fn builder_map(&self) -> &BuilderMap {
+        &self.data
+    }
+}
+
+impl BspDungeonBuilder {
+    pub fn new(new_depth: i32, width: i32, height: i32, show_mapgen: bool) -> BspDungeonBuilder {
+        BspDungeonBuilder {
+            data: BuilderMap::new(new_depth, width, height, show_mapgen),
+            rects: Vec::new(),
+        }
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
+        const MAX_ATTEMPTS: i32 = 240;
+
+        // Start with a single rectangle covering the whole map
+        self.rects.clear();
+        self.rects.push(Rect::new(
+            2,
+            2,
+            self.data.map.width - 5,
+            self.data.map.height - 5,
+        ));
+        let first_rect = self.rects[0];
+        self.add_subrects(first_rect);
+
+        // Each successful room splits its rectangle further
+        for _i in 0..MAX_ATTEMPTS {
+            let rect = self.get_random_rect(rng);
+            let candidate = self.get_random_sub_rect(rect, rng);
+
+            if self.is_possible(candidate) {
+                apply_room_to_map(&mut self.data.map, &candidate);
+                self.data.map.rooms.push(candidate);
+                self.add_subrects(rect);
+                self.data.take_snapshot();
+            }
+        }
+
+        // Join the rooms up from left to right
+        self.data.map.rooms.sort_by_key(|room| room.x1);
+        for i in 1..self.data.map.rooms.len() {
+            let room = self.data.map.rooms[i - 1];
+            let next_room = self.data.map.rooms[i];
+            let start_x = room.x1 + 1 + (rng.roll_dice(1, room.x2 - room.x1) - 1);
+            let start_y = room.y1 + 1 + (rng.roll_dice(1, room.y2 - room.y1) - 1);
+            let end_x = next_room.x1 + 1 + (rng.roll_dice(1, next_room.x2 - next_room.x1) - 1);
+            let end_y = next_room.y1 + 1 + (rng.roll_dice(1, next_room.y2 - next_room.y1) - 1);
+            self.draw_corridor(start_x, start_y, end_x, end_y);
+            self.data.take_snapshot();
+        }
+
+        let (start_x, start_y) = first_room_center(&mut self.data.map);
+        self.data.starting_position = Position {
+            x: start_x,
+            y: start_y,
+        };
+        self.data
+            .map
+            .cull_unreachable_and_place_exit(start_x, start_y);
+        self.data.take_snapshot();
+    }
+
+    /// Splits `rect` into quarters and adds them to the list.
+    fn add_subrects(&mut self, rect: Rect) {
+        let width = i32::abs(rect.x1 - rect.x2);
+        let height = i32::abs(rect.y1 - rect.y2);
+        let half_width = i32::max(width / 2, 1);
+        let half_height = i32::max(height / 2, 1);
+
+        self.rects
+            .push(Rect::new(rect.x1, rect.y1, half_width, half_height));
+        self.rects.push(Rect::new(
+            rect.x1,
+            rect.y1 + half_height,
+            half_width,
+            half_height,
+        ));
+        self.rects.push(Rect::new(
+            rect.x1 + half_width,
+            rect.y1,
+            half_width,
+            half_height,
+        ));
+        self.rects.push(Rect::new(
+            rect.x1 + half_width,
+            rect.y1 + half_height,
+            half_width,
+            half_height,
+        ));
+    }
+
+    fn get_random_rect(&mut self, rng: &mut RandomNumberGenerator) -> Rect {
+        if self.rects.len() == 1 {
+            return self.rects[0];
+        }
+        let idx = (rng.roll_dice(1, self.rects.len() as i32) - 1) as usize;
+        self.rects[idx]
+    }
+
+    /// A room of random size & offset within `rect`.
+    fn get_random_sub_rect(&self, rect: Rect, rng: &mut RandomNumberGenerator) -> Rect {
+        let mut result = rect;
+        let rect_width = i32::abs(rect.x1 - rect.x2);
+        let rect_height = i32::abs(rect.y1 - rect.y2);
+
+        let w = i32::max(3, rng.roll_dice(1, i32::min(rect_width, 10)) - 1) + 1;
+        let h = i32::max(3, rng.roll_dice(1, i32::min(rect_height, 10)) - 1) + 1;
+
+        result.x1 += rng.roll_dice(1, 6) - 1;
+        result.y1 += rng.roll_dice(1, 6) - 1;
+        result.x2 = result.x1 + w;
+        result.y2 = result.y1 + h;
+
+        result
+    }
+
+    /// True if the room fits on the map with a 2 tile margin of solid wall around it.
+    fn is_possible(&self, rect: Rect) -> bool {
+        let mut expanded = rect;
+        expanded.x1 -= 2;
+        expanded.x2 += 2;
+        expanded.y1 -= 2;
+        expanded.y2 += 2;
+
+        for y in expanded.y1..=expanded.y2 {
+            for x in expanded.x1..=expanded.x2 {
+                if x < 1 || x > self.data.map.width - 2 || y < 1 || y > self.data.map.height - 2 {
+                    return false;
+                }
+                let idx = self.data.map.xy_idx(x, y);
+                if self.data.map.tiles[idx] != TileType::Wall {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Digs a corridor stepping horizontally first, then vertically.
+    fn draw_corridor(&mut self, x1: i32, y1: i32, x2: i32, y2: i32) {
+        let mut x = x1;
+        let mut y = y1;
+
+        while x != x2 || y != y2 {
+            if x < x2 {
+                x += 1;
+            } else if x > x2 {
+                x -= 1;
+            } else if y < y2 {
+                y += 1;
+            } else if y > y2 {
+                y -= 1;
+            }
+
+            let idx = self.data.map.xy_idx(x, y);
+            self.data.map.tiles[idx] = TileType::Floor;
+        }
+    }
+}
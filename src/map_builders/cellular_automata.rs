@@ -0,0 +1,101 @@
+use super::{central_floor_position, spawn_areas, spawner, BuilderMap, MapBuilder, TileType};
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+
+/// Natural looking caverns: random noise smoothed out by a few rounds of
+/// "become a wall if surrounded by walls".
+pub struct CellularAutomataBuilder {
+    data: BuilderMap,
+    spawn_areas: Vec<Vec<usize>>,
+}
+
+impl MapBuilder for CellularAutomataBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.build(rng);
+    }
+
+    fn spawn_entities(&mut self, ecs: &mut World) {
+        for area in self.spawn_areas.iter() {
+            spawner::spawn_region(ecs, &self.data.map, area);
+        }
+    }
+
+    fn builder_map(&self) -> &BuilderMap {
+        &self.data
+    }
+}
+
+impl CellularAutomataBuilder {
+    pub fn new(
+        new_depth: i32,
+        width: i32,
+        height: i32,
+        show_mapgen: bool,
+    ) -> CellularAutomataBuilder {
+        CellularAutomataBuilder {
+            data: BuilderMap::new(new_depth, width, height, show_mapgen),
+            spawn_areas: Vec::new(),
+        }
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
+        const FLOOR_CHANCE: i32 = 55;
+        const ITERATIONS: i32 = 15;
+
+        // Start with noise, keeping the outer edge solid
+        for y in 1..self.data.map.height - 1 {
+            for x in 1..self.data.map.width - 1 {
+                let idx = self.data.map.xy_idx(x, y);
+                if rng.roll_dice(1, 100) <= FLOOR_CHANCE {
+                    self.data.map.tiles[idx] = TileType::Floor;
+                } else {
+                    self.data.map.tiles[idx] = TileType::Wall;
+                }
+            }
+        }
+
+        self.data.take_snapshot();
+
+        // Smooth it out. Crowded tiles and lonely tiles both turn to wall.
+        let width = self.data.map.width as usize;
+        for _i in 0..ITERATIONS {
+            let mut new_tiles = self.data.map.tiles.clone();
+
+            for y in 1..self.data.map.height - 1 {
+                for x in 1..self.data.map.width - 1 {
+                    let idx = self.data.map.xy_idx(x, y);
+                    let neighbors = [
+                        idx - 1,
+                        idx + 1,
+                        idx - width,
+                        idx + width,
+                        idx - width - 1,
+                        idx - width + 1,
+                        idx + width - 1,
+                        idx + width + 1,
+                    ]
+                    .iter()
+                    .filter(|n| self.data.map.tiles[**n] == TileType::Wall)
+                    .count();
+
+                    if neighbors > 4 || neighbors == 0 {
+                        new_tiles[idx] = TileType::Wall;
+                    } else {
+                        new_tiles[idx] = TileType::Floor;
+                    }
+                }
+            }
+
+            self.data.map.tiles = new_tiles;
+            self.data.take_snapshot();
+        }
+
+        self.data.starting_position = central_floor_position(&self.data.map);
+        self.data.map.cull_unreachable_and_place_exit(
+            self.data.starting_position.x,
+            self.data.starting_position.y,
+        );
+        self.data.take_snapshot();
+        self.spawn_areas = spawn_areas(&self.data.map, &self.data.starting_position);
+    }
+}
@@ -0,0 +1,140 @@
+use super::{Map, Position, Rect, TileType};
+use std::cmp::{max, min};
+
+/// Side of the square chunks that maps without rooms are split into for spawning.
+const SPAWN_CHUNK_SIZE: i32 = 8;
+
+pub fn apply_room_to_map(map: &mut Map, room: &Rect) {
+    for y in room.y1 + 1..=room.y2 {
+        for x in room.x1 + 1..=room.x2 {
+            let idx = map.xy_idx(x, y);
+            map.tiles[idx] = TileType::Floor;
+        }
+    }
+}
+
+pub fn apply_horizontal_tunnel(map: &mut Map, x1: i32, x2: i32, y: i32) {
+    for x in min(x1, x2)..=max(x1, x2) {
+        let idx = map.xy_idx(x, y);
+        if idx > 0 && idx < map.width as usize * map.height as usize {
+            map.tiles[idx] = TileType::Floor;
+        }
+    }
+}
+
+pub fn apply_vertical_tunnel(map: &mut Map, y1: i32, y2: i32, x: i32) {
+    for y in min(y1, y2)..=max(y1, y2) {
+        let idx = map.xy_idx(x, y);
+        if idx > 0 && idx < map.width as usize * map.height as usize {
+            map.tiles[idx] = TileType::Floor;
+        }
+    }
+}
+
+/// Tiles where a corridor enters a room: floor squeezed between two walls, with
+/// the room's floor on one side and the corridor's on the other.
+pub fn door_positions(map: &Map) -> Vec<(i32, i32)> {
+    let in_room = |x: i32, y: i32| {
+        map.rooms
+            .iter()
+            .any(|room| x > room.x1 && x <= room.x2 && y > room.y1 && y <= room.y2)
+    };
+    let is_floor = |x: i32, y: i32| map.tiles[map.xy_idx(x, y)] != TileType::Wall;
+    let is_wall = |x: i32, y: i32| map.tiles[map.xy_idx(x, y)] == TileType::Wall;
+
+    let mut doors = Vec::new();
+    for y in 1..map.height - 1 {
+        for x in 1..map.width - 1 {
+            // Not on the stairs, which count as floor for the neighbours
+            if map.tiles[map.xy_idx(x, y)] != TileType::Floor || in_room(x, y) {
+                continue;
+            }
+
+            let across_corridor = is_wall(x, y - 1)
+                && is_wall(x, y + 1)
+                && is_floor(x - 1, y)
+                && is_floor(x + 1, y)
+                && in_room(x - 1, y) != in_room(x + 1, y);
+            let along_corridor = is_wall(x - 1, y)
+                && is_wall(x + 1, y)
+                && is_floor(x, y - 1)
+                && is_floor(x, y + 1)
+                && in_room(x, y - 1) != in_room(x, y + 1);
+            if across_corridor || along_corridor {
+                doors.push((x, y));
+            }
+        }
+    }
+    doors
+}
+
+/// A copy of the map with every tile revealed, so the whole of it can be shown
+/// while replaying how it was built.
+pub fn snapshot(map: &Map) -> Map {
+    let mut copy = map.clone();
+    for revealed in copy.revealed_tiles.iter_mut() {
+        *revealed = true;
+    }
+    for visible in copy.visible_tiles.iter_mut() {
+        *visible = true;
+    }
+    copy
+}
+
+/// The middle of the first room, where rooms-based maps start the player.
+/// Should no room have fitted, a small one is dug in the middle of the map first.
+pub fn first_room_center(map: &mut Map) -> (i32, i32) {
+    if map.rooms.is_empty() {
+        let room = Rect::new(map.width / 2 - 3, map.height / 2 - 3, 6, 6);
+        apply_room_to_map(map, &room);
+        map.rooms.push(room);
+    }
+    map.rooms[0].center()
+}
+
+/// The floor tile closest to the middle of the map, for builders without a first room.
+pub fn central_floor_position(map: &Map) -> Position {
+    let center_x = map.width / 2;
+    let center_y = map.height / 2;
+
+    let mut best = Position {
+        x: center_x,
+        y: center_y,
+    };
+    let mut best_distance = i32::MAX;
+    for y in 1..map.height - 1 {
+        for x in 1..map.width - 1 {
+            if map.tiles[map.xy_idx(x, y)] == TileType::Floor {
+                let distance = (x - center_x) * (x - center_x) + (y - center_y) * (y - center_y);
+                if distance < best_distance {
+                    best_distance = distance;
+                    best = Position { x, y };
+                }
+            }
+        }
+    }
+
+    best
+}
+
+/// Splits the floor of a map into chunks to spawn into, skipping the chunk
+/// the player starts in.
+pub fn spawn_areas(map: &Map, start: &Position) -> Vec<Vec<usize>> {
+    let chunks_wide = (map.width + SPAWN_CHUNK_SIZE - 1) / SPAWN_CHUNK_SIZE;
+    let chunks_high = (map.height + SPAWN_CHUNK_SIZE - 1) / SPAWN_CHUNK_SIZE;
+    let start_chunk = (start.y / SPAWN_CHUNK_SIZE) * chunks_wide + (start.x / SPAWN_CHUNK_SIZE);
+
+    let mut areas: Vec<Vec<usize>> = vec![Vec::new(); (chunks_wide * chunks_high) as usize];
+    for y in 1..map.height - 1 {
+        for x in 1..map.width - 1 {
+            let idx = map.xy_idx(x, y);
+            let chunk = (y / SPAWN_CHUNK_SIZE) * chunks_wide + (x / SPAWN_CHUNK_SIZE);
+            if map.tiles[idx] == TileType::Floor && chunk != start_chunk {
+                areas[chunk as usize].push(idx);
+            }
+        }
+    }
+
+    areas.retain(|area| !area.is_empty());
+    areas
+}
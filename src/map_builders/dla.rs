@@ -0,0 +1,116 @@
+use super::{spawn_areas, spawner, BuilderMap, MapBuilder, Position, TileType};
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+
+/// Diffusion-limited aggregation: diggers wander in from random spots until they
+/// bump into the open area, then dig out the tile they were standing on. Grows
+/// branching, coral-like caves out from a small seed in the middle.
+pub struct DlaBuilder {
+    data: BuilderMap,
+    spawn_areas: Vec<Vec<usize>>,
+}
+
+impl MapBuilder for DlaBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.build(rng);
+    }
+
+    fn spawn_entities(&mut self, ecs: &mut World) {
+        for area in self.spawn_areas.iter() {
+            spawner::spawn_region(ecs, &self.data.map, area);
+        }
+    }
+
+    fn builder_map(&self) -> &BuilderMap {
+        &self.data
+    }
+}
+
+impl DlaBuilder {
+    pub fn new(new_depth: i32, width: i32, height: i32, show_mapgen: bool) -> DlaBuilder {
+        DlaBuilder {
+            data: BuilderMap::new(new_depth, width, height, show_mapgen),
+            spawn_areas: Vec::new(),
+        }
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
+        const FLOOR_PERCENT: f32 = 0.25;
+        // Each digger only adds one tile, so only every few are worth a snapshot
+        const TILES_PER_SNAPSHOT: usize = 10;
+
+        // Seed a small cross in the middle for the diggers to find
+        self.data.starting_position = Position {
+            x: self.data.map.width / 2,
+            y: self.data.map.height / 2,
+        };
+        let (start_x, start_y) = (self.data.starting_position.x, self.data.starting_position.y);
+        for (x, y) in [
+            (start_x, start_y),
+            (start_x - 1, start_y),
+            (start_x + 1, start_y),
+            (start_x, start_y - 1),
+            (start_x, start_y + 1),
+        ]
+        .iter()
+        {
+            let idx = self.data.map.xy_idx(*x, *y);
+            self.data.map.tiles[idx] = TileType::Floor;
+        }
+
+        let total_tiles = self.data.map.width * self.data.map.height;
+        let desired_floor_tiles = (FLOOR_PERCENT * total_tiles as f32) as usize;
+        let mut floor_tile_count = 5;
+
+        while floor_tile_count < desired_floor_tiles {
+            let mut digger_x = rng.roll_dice(1, self.data.map.width - 3) + 1;
+            let mut digger_y = rng.roll_dice(1, self.data.map.height - 3) + 1;
+            let mut prev_x = digger_x;
+            let mut prev_y = digger_y;
+
+            // Wander until we step onto floor; the tile we came from joins the cave
+            while self.data.map.tiles[self.data.map.xy_idx(digger_x, digger_y)] == TileType::Wall {
+                prev_x = digger_x;
+                prev_y = digger_y;
+                match rng.roll_dice(1, 4) {
+                    1 => {
+                        if digger_x > 2 {
+                            digger_x -= 1;
+                        }
+                    }
+                    2 => {
+                        if digger_x < self.data.map.width - 2 {
+                            digger_x += 1;
+                        }
+                    }
+                    3 => {
+                        if digger_y > 2 {
+                            digger_y -= 1;
+                        }
+                    }
+                    _ => {
+                        if digger_y < self.data.map.height - 2 {
+                            digger_y += 1;
+                        }
+                    }
+                }
+            }
+
+            let idx = self.data.map.xy_idx(prev_x, prev_y);
+            if self.data.map.tiles[idx] == TileType::Wall {
+                self.data.map.tiles[idx] = TileType::Floor;
+                floor_tile_count += 1;
+                if floor_tile_count % TILES_PER_SNAPSHOT == 0 {
+                    self.data.take_snapshot();
+                }
+            }
+        }
+
+        self.data.map.cull_unreachable_and_place_exit(
+            self.data.starting_position.x,
+            self.data.starting_position.y,
+        );
+        self.data.take_snapshot();
+        self.spawn_areas = spawn_areas(&self.data.map, &self.data.starting_position);
+    }
+}
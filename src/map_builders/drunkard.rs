@@ -0,0 +1,109 @@
+use super::{central_floor_position, spawn_areas, spawner, BuilderMap, MapBuilder, TileType};
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+
+/// Open, winding caves dug by "drunken" diggers stumbling about at random.
+pub struct DrunkardsWalkBuilder {
+    data: BuilderMap,
+    spawn_areas: Vec<Vec<usize>>,
+}
+
+impl MapBuilder for DrunkardsWalkBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.build(rng);
+    }
+
+    fn spawn_entities(&mut self, ecs: &mut World) {
+        for area in self.spawn_areas.iter() {
+            spawner::spawn_region(ecs, &self.data.map, area);
+        }
+    }
+
+    fn builder_map(&self) -> &BuilderMap {
+        &self.data
+    }
+}
+
+impl DrunkardsWalkBuilder {
+    pub fn new(new_depth: i32, width: i32, height: i32, show_mapgen: bool) -> DrunkardsWalkBuilder {
+        DrunkardsWalkBuilder {
+            data: BuilderMap::new(new_depth, width, height, show_mapgen),
+            spawn_areas: Vec::new(),
+        }
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
+        const DRUNKARD_LIFETIME: i32 = 400;
+        const FLOOR_PERCENT: f32 = 0.5;
+
+        // The first digger starts in the middle
+        let center_x = self.data.map.width / 2;
+        let center_y = self.data.map.height / 2;
+        let center_idx = self.data.map.xy_idx(center_x, center_y);
+        self.data.map.tiles[center_idx] = TileType::Floor;
+
+        let total_tiles = self.data.map.width * self.data.map.height;
+        let desired_floor_tiles = (FLOOR_PERCENT * total_tiles as f32) as usize;
+        let mut floor_tile_count = 1;
+
+        while floor_tile_count < desired_floor_tiles {
+            // Later diggers start somewhere already dug, so the caves stay connected
+            let (mut drunk_x, mut drunk_y) = if floor_tile_count == 1 {
+                (center_x, center_y)
+            } else {
+                self.random_floor_tile(rng)
+            };
+
+            for _i in 0..DRUNKARD_LIFETIME {
+                let idx = self.data.map.xy_idx(drunk_x, drunk_y);
+                if self.data.map.tiles[idx] == TileType::Wall {
+                    self.data.map.tiles[idx] = TileType::Floor;
+                    floor_tile_count += 1;
+                }
+
+                match rng.roll_dice(1, 4) {
+                    1 => {
+                        if drunk_x > 2 {
+                            drunk_x -= 1;
+                        }
+                    }
+                    2 => {
+                        if drunk_x < self.data.map.width - 2 {
+                            drunk_x += 1;
+                        }
+                    }
+                    3 => {
+                        if drunk_y > 2 {
+                            drunk_y -= 1;
+                        }
+                    }
+                    _ => {
+                        if drunk_y < self.data.map.height - 2 {
+                            drunk_y += 1;
+                        }
+                    }
+                }
+            }
+
+            self.data.take_snapshot();
+        }
+
+        self.data.starting_position = central_floor_position(&self.data.map);
+        self.data.map.cull_unreachable_and_place_exit(
+            self.data.starting_position.x,
+            self.data.starting_position.y,
+        );
+        self.data.take_snapshot();
+        self.spawn_areas = spawn_areas(&self.data.map, &self.data.starting_position);
+    }
+
+    fn random_floor_tile(&self, rng: &mut RandomNumberGenerator) -> (i32, i32) {
+        loop {
+            let x = rng.roll_dice(1, self.data.map.width - 3) + 1;
+            let y = rng.roll_dice(1, self.data.map.height - 3) + 1;
+            if self.data.map.tiles[self.data.map.xy_idx(x, y)] == TileType::Floor {
+                return (x, y);
+            }
+        }
+    }
+}
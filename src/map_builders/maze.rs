@@ -0,0 +1,99 @@
+use super::{spawn_areas, spawner, BuilderMap, MapBuilder, Position, TileType};
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+
+/// A perfect maze carved by a recursive backtracker. Each maze cell is a
+/// floor tile, with the walls between cells knocked through as it goes.
+pub struct MazeBuilder {
+    data: BuilderMap,
+    spawn_areas: Vec<Vec<usize>>,
+}
+
+impl MapBuilder for MazeBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.build(rng);
+    }
+
+    fn spawn_entities(&mut self, ecs: &mut World) {
+        for area in self.spawn_areas.iter() {
+            spawner::spawn_region(ecs, &self.data.map, area);
+        }
+    }
+
+    fn builder_map(&self) -> &BuilderMap {
+        &self.data
+    }
+}
+
+impl MazeBuilder {
+    pub fn new(new_depth: i32, width: i32, height: i32, show_mapgen: bool) -> MazeBuilder {
+        MazeBuilder {
+            data: BuilderMap::new(new_depth, width, height, show_mapgen),
+            spawn_areas: Vec::new(),
+        }
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
+        // Carving is one tile at a time, so only every few cells are worth a snapshot
+        const CELLS_PER_SNAPSHOT: i32 = 10;
+
+        // Cells sit on odd coordinates, leaving room for a wall between each pair
+        let grid_width = (self.data.map.width - 2) / 2;
+        let grid_height = (self.data.map.height - 2) / 2;
+        let cell_idx = |x: i32, y: i32| (y * grid_width + x) as usize;
+
+        let mut visited = vec![false; (grid_width * grid_height) as usize];
+        let mut backtrace: Vec<(i32, i32)> = vec![(0, 0)];
+        visited[0] = true;
+        self.carve(0, 0);
+        let mut cells_carved = 1;
+
+        while let Some(&(x, y)) = backtrace.last() {
+            let mut neighbors: Vec<(i32, i32)> = Vec::new();
+            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)].iter() {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx >= 0
+                    && nx < grid_width
+                    && ny >= 0
+                    && ny < grid_height
+                    && !visited[cell_idx(nx, ny)]
+                {
+                    neighbors.push((nx, ny));
+                }
+            }
+
+            if neighbors.is_empty() {
+                // Dead end; go back and try from the previous cell
+                backtrace.pop();
+            } else {
+                let pick = (rng.roll_dice(1, neighbors.len() as i32) - 1) as usize;
+                let (nx, ny) = neighbors[pick];
+                visited[cell_idx(nx, ny)] = true;
+
+                // Knock through the wall between the two cells
+                let wall_idx = self.data.map.xy_idx(x + nx + 1, y + ny + 1);
+                self.data.map.tiles[wall_idx] = TileType::Floor;
+                self.carve(nx, ny);
+                cells_carved += 1;
+                if cells_carved % CELLS_PER_SNAPSHOT == 0 {
+                    self.data.take_snapshot();
+                }
+
+                backtrace.push((nx, ny));
+            }
+        }
+
+        self.data.starting_position = Position { x: 1, y: 1 };
+        self.data.map.cull_unreachable_and_place_exit(
+            self.data.starting_position.x,
+            self.data.starting_position.y,
+        );
+        self.data.take_snapshot();
+        self.spawn_areas = spawn_areas(&self.data.map, &self.data.starting_position);
+    }
+
+    fn carve(&mut self, cell_x: i32, cell_y: i32) {
+        let idx = self.data.map.xy_idx(cell_x * 2 + 1, cell_y * 2 + 1);
+        self.data.map.tiles[idx] = TileType::Floor;
+    }
+}
@@ -0,0 +1,164 @@
+use super::raws::RawMaster;
+use super::{spawner, Map, Position, Rect, TileType};
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+
+mod common;
+use common::*;
+
+mod simple_map;
+use simple_map::SimpleMapBuilder;
+
+mod bsp_dungeon;
+use bsp_dungeon::BspDungeonBuilder;
+
+mod cellular_automata;
+use cellular_automata::CellularAutomataBuilder;
+
+mod drunkard;
+use drunkard::DrunkardsWalkBuilder;
+
+mod maze;
+use maze::MazeBuilder;
+
+mod dla;
+use dla::DlaBuilder;
+
+/// What every builder works on: the map, where the player starts on it, and
+/// the snapshots taken along the way.
+pub struct BuilderMap {
+    pub map: Map,
+    pub starting_position: Position,
+    pub history: Vec<Map>,
+    // Snapshots are only worth copying the map for if the visualizer will show them
+    show_mapgen: bool,
+}
+
+impl BuilderMap {
+    pub fn new(new_depth: i32, width: i32, height: i32, show_mapgen: bool) -> BuilderMap {
+        BuilderMap {
+            map: Map::new(new_depth, width, height),
+            starting_position: Position { x: 0, y: 0 },
+            history: Vec::new(),
+            show_mapgen,
+        }
+    }
+
+    pub fn take_snapshot(&mut self) {
+        if self.show_mapgen {
+            self.history.push(snapshot(&self.map));
+        }
+    }
+}
+
+/// A map generation algorithm. Builders own the map while they work on it,
+/// and know best where to put the player and the level's spawns.
+pub trait MapBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator);
+    fn spawn_entities(&mut self, ecs: &mut World);
+    fn builder_map(&self) -> &BuilderMap;
+
+    fn get_map(&self) -> Map {
+        self.builder_map().map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.builder_map().starting_position.clone()
+    }
+
+    /// Copies of the map taken while it was being built, oldest first.
+    fn get_snapshot_history(&self) -> Vec<Map> {
+        self.builder_map().history.clone()
+    }
+}
+
+/// Names the builders go by in the raws' level list.
+pub const BUILDER_NAMES: [&str; 6] = [
+    "simple",
+    "bsp",
+    "cellular_automata",
+    "drunkards_walk",
+    "maze",
+    "dla",
+];
+
+/// `show_mapgen` says whether the builder should keep snapshots for the visualizer.
+pub fn builder_by_name(
+    name: &str,
+    new_depth: i32,
+    width: i32,
+    height: i32,
+    show_mapgen: bool,
+) -> Option<Box<dyn MapBuilder>> {
+    match name {
+        "simple" => Some(Box::new(SimpleMapBuilder::new(
+            new_depth,
+            width,
+            height,
+            show_mapgen,
+        ))),
+        "bsp" => Some(Box::new(BspDungeonBuilder::new(
+            new_depth,
+            width,
+            height,
+            show_mapgen,
+        ))),
+        "cellular_automata" => Some(Box::new(CellularAutomataBuilder::new(
+            new_depth,
+            width,
+            height,
+            show_mapgen,
+        ))),
+        "drunkards_walk" => Some(Box::new(DrunkardsWalkBuilder::new(
+            new_depth,
+            width,
+            height,
+            show_mapgen,
+        ))),
+        "maze" => Some(Box::new(MazeBuilder::new(
+            new_depth,
+            width,
+            height,
+            show_mapgen,
+        ))),
+        "dla" => Some(Box::new(DlaBuilder::new(
+            new_depth,
+            width,
+            height,
+            show_mapgen,
+        ))),
+        _ => None,
+    }
+}
+
+pub fn random_builder(
+    new_depth: i32,
+    width: i32,
+    height: i32,
+    show_mapgen: bool,
+    rng: &mut RandomNumberGenerator,
+) -> Box<dyn MapBuilder> {
+    let roll = rng.roll_dice(1, BUILDER_NAMES.len() as i32);
+    builder_by_name(
+        BUILDER_NAMES[roll as usize - 1],
+        new_depth,
+        width,
+        height,
+        show_mapgen,
+    )
+    .unwrap()
+}
+
+/// The builder the raws ask for at this depth (`load_raws` has checked it exists),
+/// or a random one if they don't say, making a map of the size they ask for.
+pub fn level_builder(
+    new_depth: i32,
+    show_mapgen: bool,
+    rng: &mut RandomNumberGenerator,
+    raws: &RawMaster,
+) -> Box<dyn MapBuilder> {
+    let (width, height) = raws.level_dimensions(new_depth);
+    raws.level_builder_name(new_depth)
+        .and_then(|name| builder_by_name(name, new_depth, width, height, show_mapgen))
+        .unwrap_or_else(|| random_builder(new_depth, width, height, show_mapgen, rng))
+}
@@ -0,0 +1,95 @@
+use super::{
+    apply_horizontal_tunnel, apply_room_to_map, apply_vertical_tunnel, door_positions,
+    first_room_center, spawner, BuilderMap, MapBuilder, Position, Rect,
+};
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+
+pub struct SimpleMapBuilder {
+    data: BuilderMap,
+}
+
+impl MapBuilder for SimpleMapBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.rooms_and_corridors(rng);
+    }
+
+    fn spawn_entities(&mut self, ecs: &mut World) {
+        for (x, y) in door_positions(&self.data.map) {
+            spawner::door(ecs, x, y);
+        }
+
+        // The first room is where the player starts, so it is left empty
+        for room in self.data.map.rooms.iter().skip(1) {
+            spawner::spawn_room(ecs, &self.data.map, room);
+        }
+    }
+
+    fn builder_map(&self) -> &BuilderMap {
+        &self.data
+    }
+}
+
+impl SimpleMapBuilder {
+    pub fn new(new_depth: i32, width: i32, height: i32, show_mapgen: bool) -> SimpleMapBuilder {
+        SimpleMapBuilder {
+            data: BuilderMap::new(new_depth, width, height, show_mapgen),
+        }
+    }
+
+    /// Makes a new map using the algorithm from http://rogueliketutorials.com/tutorials/tcod/part-3/
+    /// This gives a handful of random rooms and corridors joining them together.
+    fn rooms_and_corridors(&mut self, rng: &mut RandomNumberGenerator) {
+        const MAX_ROOMS: i32 = 30;
+        const MIN_SIZE: i32 = 6;
+        const MAX_SIZE: i32 = 10;
+
+        for _i in 0..MAX_ROOMS {
+            let w = rng.range(MIN_SIZE, MAX_SIZE);
+            let h = rng.range(MIN_SIZE, MAX_SIZE);
+
+            let x = rng.roll_dice(1, self.data.map.width - w - 1) - 1;
+            let y = rng.roll_dice(1, self.data.map.height - h - 1) - 1;
+
+            let new_room = Rect::new(x, y, w, h);
+
+            let mut ok = true;
+            for other_room in self.data.map.rooms.iter() {
+                if new_room.intersect(other_room) {
+                    ok = false
+                }
+            }
+
+            if ok {
+                apply_room_to_map(&mut self.data.map, &new_room);
+
+                if !self.data.map.rooms.is_empty() {
+                    let (new_x, new_y) = new_room.center();
+                    let (prev_x, prev_y) =
+                        self.data.map.rooms[self.data.map.rooms.len() - 1].center();
+
+                    if rng.range(0, 1) == 1 {
+                        apply_horizontal_tunnel(&mut self.data.map, prev_x, new_x, prev_y);
+                        apply_vertical_tunnel(&mut self.data.map, prev_y, new_y, new_x);
+                    } else {
+                        apply_vertical_tunnel(&mut self.data.map, prev_y, new_y, prev_x);
+                        apply_horizontal_tunnel(&mut self.data.map, prev_x, new_x, new_y);
+                    }
+                }
+
+                self.data.map.rooms.push(new_room);
+                self.data.take_snapshot();
+            }
+        }
+
+        let (start_x, start_y) = first_room_center(&mut self.data.map);
+        self.data.starting_position = Position {
+            x: start_x,
+            y: start_y,
+        };
+        self.data
+            .map
+            .cull_unreachable_and_place_exit(start_x, start_y);
+        self.data.take_snapshot();
+    }
+}
@@ -1,4 +1,4 @@
-use super::{BlocksTile, Map, Position};
+use super::{BlocksTile, BlocksVisibility, Map, Position};
 use specs::prelude::*;
 
 pub struct MapIndexingSystem {}
@@ -8,14 +8,18 @@ impl<'a> System<'a> for MapIndexingSystem {
         WriteExpect<'a, Map>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, BlocksTile>,
+        ReadStorage<'a, BlocksVisibility>,
         Entities<'a>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut map, position, blockers, entities) = data;
+        let (mut map, position, blockers, sight_blockers, entities) = data;
 
         // Blocked from terrain
         map.populate_blocked();
+        for view_blocked in map.view_blocked.iter_mut() {
+            *view_blocked = false;
+        }
 
         // Clear tile contents
         map.clear_content_index();
@@ -30,6 +34,9 @@ impl<'a> System<'a> for MapIndexingSystem {
             if let Some(_p) = _p {
                 map.blocked[idx] = true;
             }
+            if sight_blockers.get(entity).is_some() {
+                map.view_blocked[idx] = true;
+            }
 
             // Push the entity to the appropriate index slot. It's a Copy
             // type, so we don't need to clone (we want to avoid moving it out of
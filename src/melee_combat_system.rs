@@ -1,49 +1,99 @@
-use super::{gamelog::GameLog, CombatStats, Name, SufferDamage, WantsToMelee};
-use rltk::console;
+use super::{
+    gamelog::GameLog, name_of, run_stats::RunStats, CombatStats, DefenseBonus, Equipped,
+    MeleePowerBonus, Name, SufferDamage, WantsToMelee,
+};
 use specs::prelude::*;
 
 pub struct MeleeCombatSystem {}
 
 impl<'a> System<'a> for MeleeCombatSystem {
+    #[allow(clippy::type_complexity)]
     type SystemData = (
         Entities<'a>,
+        ReadExpect<'a, Entity>, // Player entity resource
         WriteExpect<'a, GameLog>,
+        WriteExpect<'a, RunStats>,
         WriteStorage<'a, WantsToMelee>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, CombatStats>,
         WriteStorage<'a, SufferDamage>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, MeleePowerBonus>,
+        ReadStorage<'a, DefenseBonus>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut log, mut wants_melee, names, combat_stats, mut inflict_damage) = data;
+        let (
+            entities,
+            player_entity,
+            mut log,
+            mut run_stats,
+            mut wants_melee,
+            names,
+            combat_stats,
+            mut inflict_damage,
+            equipped,
+            melee_power_bonuses,
+            defense_bonuses,
+        ) = data;
 
-        for (_entity, wants_melee, name, stats) in
+        for (entity, wants_melee, name, stats) in
             (&entities, &wants_melee, &names, &combat_stats).join()
         {
             if stats.hp > 0 {
-                let target_stats = combat_stats.get(wants_melee.target).unwrap();
+                // Only creatures (things with combat stats) can be hurt
+                let target_stats = match combat_stats.get(wants_melee.target) {
+                    Some(target_stats) => target_stats,
+                    None => continue,
+                };
                 if target_stats.hp > 0 {
-                    let target_name = names.get(wants_melee.target).unwrap();
+                    let target_name = name_of(&names, wants_melee.target);
 
-                    let damage = i32::max(0, stats.power - target_stats.defense);
+                    // Add up the bonuses from everything the attacker & target have equipped
+                    let mut offensive_bonus = 0;
+                    let mut defensive_bonus = 0;
+                    for (item_entity, equipped_by) in (&entities, &equipped).join() {
+                        if equipped_by.owner == entity {
+                            if let Some(bonus) = melee_power_bonuses.get(item_entity) {
+                                offensive_bonus += bonus.power;
+                            }
+                        }
+                        if equipped_by.owner == wants_melee.target {
+                            if let Some(bonus) = defense_bonuses.get(item_entity) {
+                                defensive_bonus += bonus.defense;
+                            }
+                        }
+                    }
+
+                    let damage = i32::max(
+                        0,
+                        (stats.power + offensive_bonus) - (target_stats.defense + defensive_bonus),
+                    );
 
                     if damage == 0 {
                         log.entries.insert(
                             0,
-                            format!("{} is unable to hurt {}", &name.name, &target_name.name),
+                            format!("{} is unable to hurt {}", &name.name, &target_name),
                         );
                     } else {
                         log.entries.insert(
                             0,
-                            format!(
-                                "{} hits {}, for {} hp",
-                                &name.name, &target_name.name, damage
-                            ),
+                            format!("{} hits {}, for {} hp", &name.name, &target_name, damage),
+                        );
+
+                        SufferDamage::new_damage(
+                            &mut inflict_damage,
+                            wants_melee.target,
+                            damage,
+                            Some(entity),
                         );
 
-                        inflict_damage
-                            .insert(wants_melee.target, SufferDamage { amount: damage })
-                            .expect("@ERROR: Unable to do damage");
+                        if entity == *player_entity {
+                            run_stats.damage_dealt += damage;
+                        }
+                        if wants_melee.target == *player_entity {
+                            run_stats.last_hit_by = Some(name.name.to_string());
+                        }
                     }
                 }
             }
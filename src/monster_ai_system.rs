@@ -1,7 +1,36 @@
-use super::{Map, Monster, Position, RunState, Viewshed, WantsToMelee};
-use rltk::Point;
+use super::raws::{RawMaster, Reaction};
+use super::{
+    AiBehaviour, AiState, CombatStats, Confusion, Door, EntityMoved, Faction, Map, Monster,
+    MonsterAiState, MyTurn, Position, Provoked, Viewshed, WantsToMelee, WantsToOpenDoor,
+};
+use rltk::{BaseMap, Point, RandomNumberGenerator};
 use specs::prelude::*;
 
+// What a monster does with its turn, once its state has been worked out
+enum Action {
+    Attack(Entity),
+    StepTo(usize),
+    Wait,
+}
+
+/// How far allies let the player get before following them.
+const FOLLOW_DISTANCE: f32 = 2.5;
+
+/// How `me` reacts to `them`: whatever my faction says, unless they picked a
+/// fight with me, in which case I won't ignore them any more.
+pub fn reaction(
+    raws: &RawMaster,
+    mine: &Faction,
+    provoked: Option<&Provoked>,
+    them: Entity,
+    theirs: &Faction,
+) -> Reaction {
+    match raws.faction_reaction(&mine.name, &theirs.name) {
+        Reaction::Ignore if provoked.is_some_and(|p| p.by == them) => Reaction::Attack,
+        reaction => reaction,
+    }
+}
+
 pub struct MonsterAI {}
 
 impl<'a> System<'a> for MonsterAI {
@@ -10,12 +39,23 @@ impl<'a> System<'a> for MonsterAI {
         WriteExpect<'a, Map>,
         ReadExpect<'a, Point>,  // Player Position resource
         ReadExpect<'a, Entity>, // Player entity resource
-        ReadExpect<'a, RunState>,
+        ReadExpect<'a, RawMaster>,
+        WriteExpect<'a, RandomNumberGenerator>,
         Entities<'a>,
         WriteStorage<'a, Viewshed>,
         ReadStorage<'a, Monster>,
+        ReadStorage<'a, MyTurn>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, WantsToMelee>,
+        WriteStorage<'a, Confusion>,
+        ReadStorage<'a, Door>,
+        WriteStorage<'a, WantsToOpenDoor>,
+        WriteStorage<'a, EntityMoved>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, AiBehaviour>,
+        WriteStorage<'a, MonsterAiState>,
+        ReadStorage<'a, Faction>,
+        ReadStorage<'a, Provoked>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -23,62 +63,245 @@ impl<'a> System<'a> for MonsterAI {
             mut map,
             player_pos,
             player_entity,
-            runstate,
+            raws,
+            mut rng,
             entities,
             mut viewshed,
             monster,
+            turns,
             mut position,
             mut wants_to_melee,
+            mut confused,
+            doors,
+            mut wants_to_open,
+            mut moved,
+            combat_stats,
+            behaviours,
+            mut ai_states,
+            factions,
+            provoked,
         ) = data;
 
-        // Only run if it's the monster's turn
-        if *runstate != RunState::MonsterTurn {
-            return;
-        }
+        // Allies are anything on the player's side
+        let player_faction = factions.get(*player_entity).map(|f| f.name.clone());
 
-        for (entity, mut viewshed, _monster, mut pos) in
-            (&entities, &mut viewshed, &monster, &mut position).join()
+        for (entity, viewshed, _monster, _turn, pos, behaviour, ai, faction) in (
+            &entities,
+            &mut viewshed,
+            &monster,
+            &turns,
+            &mut position,
+            &behaviours,
+            &mut ai_states,
+            &factions,
+        )
+            .join()
         {
-            // Find out distance betweenus(monster) and player
-            let distance =
-                rltk::DistanceAlg::Pythagoras.distance2d(Point::new(pos.x, pos.y), *player_pos);
-            // Attack if player gets too close
-            if distance < 1.5 {
-                wants_to_melee
-                    .insert(
-                        entity,
-                        WantsToMelee {
-                            target: *player_entity,
-                        },
-                    )
-                    .expect("@ERROR: Unable to insert attack to player");
+            // Confused monsters lose their turn until the confusion wears off
+            let mut can_act = true;
+            if let Some(confusion) = confused.get_mut(entity) {
+                confusion.turns -= 1;
+                if confusion.turns < 1 {
+                    confused.remove(entity);
+                }
+                can_act = false;
             }
-            // If we can see the player
-            else if viewshed.visible_tiles.contains(&*player_pos) {
-                // Path to player by finding a path from our (monster) position to the player position. Works with maps indices
-                let path = rltk::a_star_search(
-                    map.xy_idx(pos.x, pos.y) as i32,
-                    map.xy_idx(player_pos.x, player_pos.y) as i32,
-                    &mut *map,
-                );
-
-                if path.success && path.steps.len() > 1 {
-                    // Find current position index
-                    let mut idx = map.xy_idx(pos.x, pos.y);
-                    // Unblock it since we are leaving
-                    map.blocked[idx] = false;
-                    // Convert 1st step of path to xy coords (0 step is current position)
-                    // and move there
-                    pos.x = path.steps[1] % map.width;
-                    pos.y = path.steps[1] / map.width;
-                    // Recalculate current index after move
-                    idx = map.xy_idx(pos.x, pos.y);
-                    // And block it since we are now occuping the tile
-                    map.blocked[idx] = true;
-                    // We moved so we invalidated our viewshed
-                    viewshed.dirty = true;
+            if !can_act {
+                continue;
+            }
+
+            let here = Point::new(pos.x, pos.y);
+            let here_idx = map.xy_idx(pos.x, pos.y);
+            let badly_hurt = combat_stats
+                .get(entity)
+                .is_some_and(|stats| stats.hp * 100 < stats.max_hp * behaviour.flee_below);
+
+            // The nearest creature in view to fight, and the nearest to get away from
+            let mut target: Option<(Entity, Point, f32)> = None;
+            let mut threat: Option<(Point, f32)> = None;
+            for tile in viewshed.visible_tiles.iter() {
+                let distance = rltk::DistanceAlg::Pythagoras.distance2d(here, *tile);
+                for other in map.tile_content[map.xy_idx(tile.x, tile.y)].iter() {
+                    let their_faction = match factions.get(*other) {
+                        Some(their_faction) if *other != entity => their_faction,
+                        _ => continue,
+                    };
+                    match reaction(&raws, faction, provoked.get(entity), *other, their_faction) {
+                        Reaction::Attack if target.map_or(true, |t| distance < t.2) => {
+                            target = Some((*other, *tile, distance))
+                        }
+                        Reaction::Flee if threat.map_or(true, |t| distance < t.1) => {
+                            threat = Some((*tile, distance))
+                        }
+                        _ => {}
+                    }
                 }
             }
+            // Anything worth fighting is worth running from when badly hurt
+            let flee_from = threat
+                .map(|t| t.0)
+                .or_else(|| target.filter(|_| badly_hurt).map(|t| t.1));
+
+            ai.state = next_state(
+                ai.state,
+                behaviour,
+                target.map(|t| t.1),
+                flee_from.is_some(),
+            );
+
+            let adjacent_target = target.filter(|t| t.2 < 1.5).map(|t| t.0);
+            let is_ally = player_faction.as_ref() == Some(&faction.name);
+            let action = match ai.state {
+                // Attack the target if it gets too close, otherwise close in
+                AiState::Chase { x, y } => match adjacent_target {
+                    Some(victim) => Action::Attack(victim),
+                    None => path_step(&mut map, here, Point::new(x, y)),
+                },
+
+                // Run for it, but fight back when cornered
+                AiState::Flee => match flee_from.and_then(|from| flee_step(&map, here_idx, from)) {
+                    Some(idx) => Action::StepTo(idx),
+                    None => adjacent_target.map_or(Action::Wait, Action::Attack),
+                },
+
+                AiState::Search { x, y, turns } => {
+                    let action = path_step(&mut map, here, Point::new(x, y));
+                    // Give up on arriving, running out of time, or finding no way there
+                    ai.state = match action {
+                        Action::StepTo(_) if turns > 1 => AiState::Search {
+                            x,
+                            y,
+                            turns: turns - 1,
+                        },
+                        _ => AiState::Idle,
+                    };
+                    action
+                }
+
+                // Allies keep up with the player when there's nothing to fight
+                AiState::Idle
+                    if is_ally
+                        && rltk::DistanceAlg::Pythagoras.distance2d(here, *player_pos)
+                            > FOLLOW_DISTANCE =>
+                {
+                    path_step(&mut map, here, *player_pos)
+                }
+
+                AiState::Idle if behaviour.wanders => {
+                    let exits = map.get_available_exits(here_idx as i32);
+                    if exits.is_empty() {
+                        Action::Wait
+                    } else {
+                        let pick = (rng.roll_dice(1, exits.len() as i32) - 1) as usize;
+                        Action::StepTo(exits[pick].0 as usize)
+                    }
+                }
+                AiState::Idle => Action::Wait,
+            };
+
+            match action {
+                Action::Attack(victim) => {
+                    wants_to_melee
+                        .insert(entity, WantsToMelee { target: victim })
+                        .expect("@ERROR: Unable to insert attack");
+                }
+                Action::StepTo(idx) => {
+                    let closed_door = map.tile_content[idx]
+                        .iter()
+                        .find(|e| doors.get(**e).is_some_and(|door| !door.open))
+                        .copied();
+
+                    if let Some(door) = closed_door {
+                        // The way is shut, so this turn goes on opening it
+                        wants_to_open
+                            .insert(entity, WantsToOpenDoor { door })
+                            .expect("@ERROR: Unable to insert want to open door");
+                    } else if !map.blocked[idx] {
+                        // Unblock our tile since we are leaving, and block the new one
+                        map.blocked[here_idx] = false;
+                        pos.x = idx as i32 % map.width;
+                        pos.y = idx as i32 / map.width;
+                        map.blocked[idx] = true;
+                        // We moved, so our viewshed is stale and we may have stepped on something
+                        viewshed.dirty = true;
+                        moved
+                            .insert(entity, EntityMoved {})
+                            .expect("@ERROR: Unable to insert moved marker");
+                    }
+                }
+                Action::Wait => {}
+            }
+        }
+    }
+}
+
+// Works out a monster's state from what it can see right now
+fn next_state(
+    state: AiState,
+    behaviour: &AiBehaviour,
+    target: Option<Point>,
+    fleeing: bool,
+) -> AiState {
+    if fleeing {
+        return AiState::Flee;
+    }
+    if let Some(target) = target {
+        return AiState::Chase {
+            x: target.x,
+            y: target.y,
+        };
+    }
+
+    match state {
+        // Lost sight of the target, so go and look where it was last seen
+        AiState::Chase { x, y } if behaviour.search_turns > 0 => AiState::Search {
+            x,
+            y,
+            turns: behaviour.search_turns,
+        },
+        AiState::Chase { .. } | AiState::Flee => AiState::Idle,
+        _ => state,
+    }
+}
+
+// The first step of the path from `from` to `to`, if there is one
+fn path_step(map: &mut Map, from: Point, to: Point) -> Action {
+    // Whoever we're after blocks their own tile, which would leave no way to it
+    let to_idx = map.xy_idx(to.x, to.y);
+    let to_blocked = map.blocked[to_idx];
+    map.blocked[to_idx] = false;
+
+    // Path by finding a path from our (monster) position to the target. Works with maps indices
+    let path = rltk::a_star_search(map.xy_idx(from.x, from.y) as i32, to_idx as i32, &mut *map);
+    map.blocked[to_idx] = to_blocked;
+
+    if path.success && path.steps.len() > 1 {
+        // 0 step is current position
+        Action::StepTo(path.steps[1] as usize)
+    } else {
+        Action::Wait
+    }
+}
+
+// The neighbouring tile that gets furthest from `threat`, if any is further than here
+fn flee_step(map: &Map, here_idx: usize, threat: Point) -> Option<usize> {
+    let distance_from_threat = |idx: usize| {
+        let x = idx as i32 % map.width;
+        let y = idx as i32 / map.width;
+        rltk::DistanceAlg::Pythagoras.distance2d(Point::new(x, y), threat)
+    };
+
+    let mut best = (here_idx, distance_from_threat(here_idx));
+    for (exit, _cost) in map.get_available_exits(here_idx as i32) {
+        let distance = distance_from_threat(exit as usize);
+        if distance > best.1 {
+            best = (exit as usize, distance);
         }
     }
+
+    if best.0 == here_idx {
+        None
+    } else {
+        Some(best.0)
+    }
 }
@@ -0,0 +1,92 @@
+use super::{gamelog::GameLog, Hidden, MyTurn, Name, Position, Viewshed};
+use rltk::{Point, RandomNumberGenerator};
+use specs::prelude::*;
+
+/// Chance (one in this many) each turn of noticing each hidden thing in sight.
+pub const PASSIVE_PERCEPTION_ODDS: i32 = 24;
+
+/// Chance (one in this many) of finding each hidden thing in sight when searching.
+pub const SEARCH_ODDS: i32 = 4;
+
+/// Rolls a one in `odds` chance for every hidden entity the viewshed covers,
+/// revealing those that succeed. Returns how many were found.
+#[allow(clippy::too_many_arguments)]
+pub fn spot_hidden(
+    viewshed: &Viewshed,
+    odds: i32,
+    entities: &Entities,
+    hidden: &mut WriteStorage<Hidden>,
+    positions: &ReadStorage<Position>,
+    names: &ReadStorage<Name>,
+    rng: &mut RandomNumberGenerator,
+    log: &mut GameLog,
+) -> i32 {
+    let in_sight: Vec<Entity> = (entities, &*hidden, positions)
+        .join()
+        .filter(|(_entity, _hidden, pos)| {
+            viewshed.visible_tiles.contains(&Point::new(pos.x, pos.y))
+        })
+        .map(|(entity, _hidden, _pos)| entity)
+        .collect();
+
+    let mut found = 0;
+    for entity in in_sight {
+        if rng.roll_dice(1, odds) == 1 {
+            hidden.remove(entity);
+            found += 1;
+            if let Some(name) = names.get(entity) {
+                log.entries.insert(0, format!("You spot a {}.", name.name));
+            }
+        }
+    }
+    found
+}
+
+/// Gives the player a chance to notice hidden things around them on each of their turns.
+pub struct PerceptionSystem {}
+
+impl<'a> System<'a> for PerceptionSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Entity>, // Player entity resource
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, RandomNumberGenerator>,
+        Entities<'a>,
+        ReadStorage<'a, MyTurn>,
+        ReadStorage<'a, Viewshed>,
+        WriteStorage<'a, Hidden>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Name>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            player_entity,
+            mut log,
+            mut rng,
+            entities,
+            turns,
+            viewsheds,
+            mut hidden,
+            positions,
+            names,
+        ) = data;
+
+        if turns.get(*player_entity).is_none() {
+            return;
+        }
+
+        if let Some(viewshed) = viewsheds.get(*player_entity) {
+            spot_hidden(
+                viewshed,
+                PASSIVE_PERCEPTION_ODDS,
+                &entities,
+                &mut hidden,
+                &positions,
+                &names,
+                &mut rng,
+                &mut log,
+            );
+        }
+    }
+}
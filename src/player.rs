@@ -1,9 +1,16 @@
-use super::{CombatStats, Map, Player, Position, RunState, State, Viewshed, WantsToMelee};
-use rltk::{Point, Rltk, VirtualKeyCode};
+use super::monster_ai_system;
+use super::raws::{RawMaster, Reaction};
+use super::{
+    autopilot, camera, gamelog::GameLog, perception_system, CombatStats, Door, EntityMoved,
+    Faction, Hidden, HungerClock, HungerState, Item, Map, Monster, Name, Player, Position,
+    Provoked, RunState, State, TileType, Viewshed, WantsToMelee, WantsToOpenDoor,
+    WantsToPickupItem,
+};
+use rltk::{Point, RandomNumberGenerator, Rltk, VirtualKeyCode};
 use specs::prelude::*;
 use std::cmp::{max, min};
 
-fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
+pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
     let mut positions = ecs.write_storage::<Position>();
     let mut players = ecs.write_storage::<Player>();
     let mut viewsheds = ecs.write_storage::<Viewshed>();
@@ -11,14 +18,43 @@ fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
     let combat_stats = ecs.read_storage::<CombatStats>();
     let map = ecs.fetch::<Map>();
     let mut wants_to_melee = ecs.write_storage::<WantsToMelee>();
+    let doors = ecs.read_storage::<Door>();
+    let mut wants_to_open = ecs.write_storage::<WantsToOpenDoor>();
+    let mut moved = ecs.write_storage::<EntityMoved>();
+    let factions = ecs.read_storage::<Faction>();
+    let provoked = ecs.read_storage::<Provoked>();
+    let raws = ecs.fetch::<RawMaster>();
+
+    // Allies the player trades places with, and the spot each one moves to
+    let mut swaps: Vec<(Entity, i32, i32)> = Vec::new();
 
     for (entity, _player, pos, viewshed) in
         (&entities, &mut players, &mut positions, &mut viewsheds).join()
     {
         let destination_idx = map.xy_idx(pos.x + delta_x, pos.y + delta_y);
+        let player_faction = factions.get(entity);
 
         // Check if we are attacking something by `moving` into it
         for potential_target in map.tile_content[destination_idx].iter() {
+            // Allies get out of the way instead, unless something has turned them on the player
+            let is_ally = match (player_faction, factions.get(*potential_target)) {
+                (Some(mine), Some(theirs)) => {
+                    theirs.name == mine.name
+                        && monster_ai_system::reaction(
+                            &raws,
+                            theirs,
+                            provoked.get(*potential_target),
+                            entity,
+                            mine,
+                        ) != Reaction::Attack
+                }
+                _ => false,
+            };
+            if is_ally {
+                swaps.push((*potential_target, pos.x, pos.y));
+                break;
+            }
+
             let target = combat_stats.get(*potential_target);
             if let Some(_target) = target {
                 // Found something with combat stats in destination. Attack it and then
@@ -33,15 +69,31 @@ fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
                     .expect("@ERROR: Add target failed");
                 return; // So we dont'move after attack.
             }
+
+            // Walking into a closed door opens it instead
+            if doors.get(*potential_target).is_some_and(|door| !door.open) {
+                wants_to_open
+                    .insert(
+                        entity,
+                        WantsToOpenDoor {
+                            door: *potential_target,
+                        },
+                    )
+                    .expect("@ERROR: Unable to insert want to open door");
+                return;
+            }
         }
 
         // Check for blocking before moving
-        if !map.blocked[destination_idx] {
-            pos.x = min(79, max(0, pos.x + delta_x));
-            pos.y = min(49, max(0, pos.y + delta_y));
+        if !swaps.is_empty() || !map.blocked[destination_idx] {
+            pos.x = min(map.width - 1, max(0, pos.x + delta_x));
+            pos.y = min(map.height - 1, max(0, pos.y + delta_y));
 
-            // We moved so recalculate map visibility
+            // We moved so recalculate map visibility, and check for traps
             viewshed.dirty = true;
+            moved
+                .insert(entity, EntityMoved {})
+                .expect("@ERROR: Unable to insert moved marker");
 
             // Write player position resource
             let mut ppos = ecs.write_resource::<Point>();
@@ -49,9 +101,180 @@ fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
             ppos.y = pos.y;
         }
     }
+
+    for (ally, x, y) in swaps {
+        if let Some(pos) = positions.get_mut(ally) {
+            pos.x = x;
+            pos.y = y;
+        }
+        if let Some(viewshed) = viewsheds.get_mut(ally) {
+            viewshed.dirty = true;
+        }
+        moved
+            .insert(ally, EntityMoved {})
+            .expect("@ERROR: Unable to insert moved marker");
+    }
+}
+
+/// Queues a pickup of the first item on the player's tile.
+/// Returns the state to move to, so an empty tile doesn't cost a turn.
+pub fn get_item(ecs: &mut World) -> RunState {
+    let player_pos = ecs.fetch::<Point>();
+    let player_entity = ecs.fetch::<Entity>();
+    let map = ecs.fetch::<Map>();
+    let items = ecs.read_storage::<Item>();
+    let mut log = ecs.fetch_mut::<GameLog>();
+
+    let idx = map.xy_idx(player_pos.x, player_pos.y);
+    let target_item = map.tile_content[idx]
+        .iter()
+        .find(|entity| items.get(**entity).is_some());
+
+    match target_item {
+        None => {
+            log.entries
+                .insert(0, "There is nothing here to pick up.".to_string());
+            RunState::AwaitingInput
+        }
+        Some(item) => {
+            let mut pickup = ecs.write_storage::<WantsToPickupItem>();
+            pickup
+                .insert(
+                    *player_entity,
+                    WantsToPickupItem {
+                        collected_by: *player_entity,
+                        item: *item,
+                    },
+                )
+                .expect("@ERROR: Unable to insert want to pickup");
+            RunState::PlayerTurn
+        }
+    }
+}
+
+/// Heads down if the player is standing on the stairs.
+pub fn try_next_level(ecs: &mut World) -> RunState {
+    let player_pos = ecs.fetch::<Point>();
+    let map = ecs.fetch::<Map>();
+    let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+
+    if map.tiles[player_idx] == TileType::DownStairs {
+        RunState::NextLevel
+    } else {
+        let mut log = ecs.fetch_mut::<GameLog>();
+        log.entries
+            .insert(0, "There is no way down from here.".to_string());
+        RunState::AwaitingInput
+    }
+}
+
+/// The first monster out to get the player standing somewhere they can see, if any.
+/// Allies and creatures that haven't been provoked don't count.
+pub fn monster_in_view(ecs: &World) -> Option<Entity> {
+    let player_entity = ecs.fetch::<Entity>();
+    let raws = ecs.fetch::<RawMaster>();
+    let viewsheds = ecs.read_storage::<Viewshed>();
+    let monsters = ecs.read_storage::<Monster>();
+    let positions = ecs.read_storage::<Position>();
+    let factions = ecs.read_storage::<Faction>();
+    let provoked = ecs.read_storage::<Provoked>();
+    let entities = ecs.entities();
+
+    let viewshed = viewsheds.get(*player_entity)?;
+    let player_faction = factions.get(*player_entity)?;
+    (&entities, &monsters, &positions, &factions)
+        .join()
+        .find(|(entity, _monster, pos, faction)| {
+            viewshed.visible_tiles.contains(&Point::new(pos.x, pos.y))
+                && monster_ai_system::reaction(
+                    &raws,
+                    faction,
+                    provoked.get(*entity),
+                    *player_entity,
+                    player_faction,
+                ) == Reaction::Attack
+        })
+        .map(|(entity, _monster, _pos, _faction)| entity)
+}
+
+/// Passes the turn. Resting with nothing in sight recovers 1 hp, unless hunger gets in the way.
+pub fn skip_turn(ecs: &mut World) -> RunState {
+    let player_entity = *ecs.fetch::<Entity>();
+    let hungry = ecs
+        .read_storage::<HungerClock>()
+        .get(player_entity)
+        .is_some_and(|clock| matches!(clock.state, HungerState::Hungry | HungerState::Starving));
+
+    if !hungry && monster_in_view(ecs).is_none() {
+        let mut combat_stats = ecs.write_storage::<CombatStats>();
+        if let Some(stats) = combat_stats.get_mut(player_entity) {
+            stats.hp = i32::min(stats.hp + 1, stats.max_hp);
+        }
+    }
+    RunState::PlayerTurn
+}
+
+/// Spends the turn looking hard for hidden things in sight.
+pub fn search(ecs: &mut World) -> RunState {
+    let player_entity = ecs.fetch::<Entity>();
+    let viewsheds = ecs.read_storage::<Viewshed>();
+    let mut log = ecs.fetch_mut::<GameLog>();
+
+    if let Some(viewshed) = viewsheds.get(*player_entity) {
+        log.entries.insert(0, "You search the area.".to_string());
+        let found = perception_system::spot_hidden(
+            viewshed,
+            perception_system::SEARCH_ODDS,
+            &ecs.entities(),
+            &mut ecs.write_storage::<Hidden>(),
+            &ecs.read_storage::<Position>(),
+            &ecs.read_storage::<Name>(),
+            &mut ecs.write_resource::<RandomNumberGenerator>(),
+            &mut log,
+        );
+        if found == 0 {
+            log.entries.insert(0, "You find nothing.".to_string());
+        }
+    }
+    RunState::PlayerTurn
+}
+
+// Whether the player has seen the tile at `pos`
+fn is_revealed(ecs: &World, pos: Point) -> bool {
+    let map = ecs.fetch::<Map>();
+    pos.x >= 0
+        && pos.x < map.width
+        && pos.y >= 0
+        && pos.y < map.height
+        && map.revealed_tiles[map.xy_idx(pos.x, pos.y)]
 }
 
 pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
+    // While the autopilot is on any key or click takes back control, otherwise it walks for us
+    if autopilot::is_active(&gs.ecs) {
+        if ctx.key.is_some() || ctx.left_click {
+            autopilot::stop(&mut gs.ecs);
+            return RunState::AwaitingInput;
+        }
+        if let Some(runstate) = autopilot::step(&mut gs.ecs) {
+            return runstate;
+        }
+        return RunState::AwaitingInput;
+    }
+
+    // Walk to the clicked tile. Clicks on the log panel or on unexplored tiles are ignored.
+    if ctx.left_click {
+        let (x, y) = ctx.mouse_pos();
+        if y >= camera::VIEW_HEIGHT {
+            return RunState::AwaitingInput;
+        }
+        let destination = camera::screen_to_world(&gs.ecs, x, y);
+        if !is_revealed(&gs.ecs, destination) {
+            return RunState::AwaitingInput;
+        }
+        return autopilot::start_travel(&mut gs.ecs, destination);
+    }
+
     // Player movement
     match ctx.key {
         None => return RunState::AwaitingInput, // Nothing happened
@@ -83,6 +306,33 @@ pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
 
             VirtualKeyCode::Numpad1 | VirtualKeyCode::B => try_move_player(-1, 1, &mut gs.ecs),
 
+            // Wait a turn
+            VirtualKeyCode::Numpad5 | VirtualKeyCode::Space => return skip_turn(&mut gs.ecs),
+
+            // Look for hidden things
+            VirtualKeyCode::S => return search(&mut gs.ecs),
+
+            // Explore until something interesting turns up
+            VirtualKeyCode::X => return autopilot::start_explore(&mut gs.ecs),
+
+            // Take the stairs down
+            VirtualKeyCode::Period => return try_next_level(&mut gs.ecs),
+
+            // Pick up an item
+            VirtualKeyCode::G => return get_item(&mut gs.ecs),
+
+            // Open the inventory
+            VirtualKeyCode::I => return RunState::ShowInventory,
+
+            // Drop an item
+            VirtualKeyCode::D => return RunState::ShowDropItem,
+
+            // Remove an equipped item
+            VirtualKeyCode::R => return RunState::ShowRemoveItem,
+
+            // Save the game
+            VirtualKeyCode::Escape => return RunState::SaveGame,
+
             _ => return RunState::AwaitingInput,
         },
     }
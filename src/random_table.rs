@@ -0,0 +1,52 @@
+use rltk::RandomNumberGenerator;
+
+pub struct RandomEntry {
+    name: String,
+    weight: i32,
+}
+
+/// A list of names, each picked with a chance proportional to its weight.
+#[derive(Default)]
+pub struct RandomTable {
+    entries: Vec<RandomEntry>,
+    total_weight: i32,
+}
+
+impl RandomTable {
+    pub fn new() -> RandomTable {
+        RandomTable {
+            entries: Vec::new(),
+            total_weight: 0,
+        }
+    }
+
+    /// Adds an entry. Entries that can never be picked (weight < 1) are skipped.
+    pub fn add<S: ToString>(mut self, name: S, weight: i32) -> RandomTable {
+        if weight > 0 {
+            self.total_weight += weight;
+            self.entries.push(RandomEntry {
+                name: name.to_string(),
+                weight,
+            });
+        }
+        self
+    }
+
+    /// Picks an entry, or `None` if the table is empty.
+    pub fn roll(&self, rng: &mut RandomNumberGenerator) -> Option<String> {
+        if self.total_weight == 0 {
+            return None;
+        }
+
+        // Walk the entries until the roll falls inside one's share of the total weight
+        let mut roll = rng.roll_dice(1, self.total_weight) - 1;
+        for entry in self.entries.iter() {
+            if roll < entry.weight {
+                return Some(entry.name.clone());
+            }
+            roll -= entry.weight;
+        }
+
+        None
+    }
+}
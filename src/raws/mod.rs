@@ -0,0 +1,377 @@
+use super::map::{DEFAULT_MAP_HEIGHT, DEFAULT_MAP_WIDTH};
+use super::map_builders::BUILDER_NAMES;
+use super::spawner;
+use super::EquipmentSlot;
+use rltk::RGB;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+
+/// Where designers edit the spawn definitions. Read on every start, so changes
+/// don't need a rebuild.
+const RAW_PATH: &str = "./raws/spawns.json";
+
+/// Copy of the definitions baked into the binary, used when `RAW_PATH` isn't
+/// there (e.g. a build run from another directory, or on the web).
+const EMBEDDED_RAWS: &str = include_str!("../../raws/spawns.json");
+
+/// Smallest width or height a level may ask for.
+const MIN_MAP_SIZE: i32 = 20;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Raws {
+    pub monsters: Vec<MonsterRaw>,
+    pub items: Vec<ItemRaw>,
+    #[serde(default)]
+    pub traps: Vec<TrapRaw>,
+    #[serde(default)]
+    pub levels: Vec<LevelRaw>,
+    #[serde(default)]
+    pub faction_table: Vec<FactionRaw>,
+}
+
+/// What a creature does about another one it can see.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Reaction {
+    Ignore,
+    Attack,
+    Flee,
+}
+
+/// The `responses` key for every faction not listed by name.
+const DEFAULT_RESPONSE: &str = "Default";
+
+/// How members of a faction react to each other faction, by name.
+/// `DEFAULT_RESPONSE` covers any faction not listed.
+#[derive(Deserialize, Debug, Clone)]
+pub struct FactionRaw {
+    pub name: String,
+    pub responses: HashMap<String, Reaction>,
+}
+
+/// Pins the map builder and/or size used at a depth. Anything left out is
+/// picked at random (builder) or defaulted (size).
+#[derive(Deserialize, Debug, Clone)]
+pub struct LevelRaw {
+    pub depth: i32,
+    #[serde(default)]
+    pub builder: Option<String>,
+    #[serde(default)]
+    pub width: Option<i32>,
+    #[serde(default)]
+    pub height: Option<i32>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RenderableRaw {
+    pub glyph: String,
+    pub fg: String,
+    pub bg: String,
+    pub order: i32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MonsterStatsRaw {
+    pub max_hp: i32,
+    pub defense: i32,
+    pub power: i32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MonsterRaw {
+    pub name: String,
+    pub renderable: RenderableRaw,
+    pub stats: MonsterStatsRaw,
+    pub vision_range: i32,
+    // Name of an entry in the faction table
+    pub faction: String,
+    // Energy gained per tick, see `initiative_system`. Normal speed if left out.
+    #[serde(default)]
+    pub speed: Option<i32>,
+    // Experience for killing one
+    #[serde(default)]
+    pub xp: i32,
+    // Stands still and gives up straight away if left out
+    #[serde(default)]
+    pub ai: AiRaw,
+    pub min_depth: i32,
+    pub max_depth: i32,
+    pub spawn_weight: i32,
+}
+
+/// See `AiBehaviour`.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct AiRaw {
+    #[serde(default)]
+    pub wanders: bool,
+    #[serde(default)]
+    pub flee_below: i32,
+    #[serde(default)]
+    pub search_turns: i32,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ItemEffectsRaw {
+    #[serde(default)]
+    pub healing: Option<i32>,
+    #[serde(default)]
+    pub damage: Option<i32>,
+    #[serde(default)]
+    pub range: Option<i32>,
+    #[serde(default)]
+    pub area_of_effect: Option<i32>,
+    #[serde(default)]
+    pub confusion: Option<i32>,
+    #[serde(default)]
+    pub food: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EquippableRaw {
+    pub slot: EquipmentSlot,
+    #[serde(default)]
+    pub power_bonus: i32,
+    #[serde(default)]
+    pub defense_bonus: i32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItemRaw {
+    pub name: String,
+    pub renderable: RenderableRaw,
+    #[serde(default)]
+    pub consumable: bool,
+    #[serde(default)]
+    pub effects: ItemEffectsRaw,
+    #[serde(default)]
+    pub equippable: Option<EquippableRaw>,
+    pub min_depth: i32,
+    pub max_depth: i32,
+    pub spawn_weight: i32,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct TrapEffectsRaw {
+    #[serde(default)]
+    pub damage: Option<i32>,
+    #[serde(default)]
+    pub teleport: bool,
+    // Radius the alarm is heard in
+    #[serde(default)]
+    pub alarm: Option<i32>,
+}
+
+/// A hidden trap, set off by walking onto it.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TrapRaw {
+    pub name: String,
+    pub renderable: RenderableRaw,
+    #[serde(default)]
+    pub effects: TrapEffectsRaw,
+    // Disarmed after going off once
+    #[serde(default)]
+    pub single_activation: bool,
+    pub min_depth: i32,
+    pub max_depth: i32,
+    pub spawn_weight: i32,
+}
+
+/// Registry of every spawnable definition, looked up by name.
+/// Lives in the ECS as a resource.
+pub struct RawMaster {
+    raws: Raws,
+    monster_index: HashMap<String, usize>,
+    item_index: HashMap<String, usize>,
+    trap_index: HashMap<String, usize>,
+    faction_index: HashMap<String, usize>,
+}
+
+impl RawMaster {
+    pub fn new(raws: Raws) -> RawMaster {
+        let mut monster_index = HashMap::new();
+        for (i, monster) in raws.monsters.iter().enumerate() {
+            monster_index.insert(monster.name.clone(), i);
+        }
+
+        let mut item_index = HashMap::new();
+        for (i, item) in raws.items.iter().enumerate() {
+            item_index.insert(item.name.clone(), i);
+        }
+
+        let mut trap_index = HashMap::new();
+        for (i, trap) in raws.traps.iter().enumerate() {
+            trap_index.insert(trap.name.clone(), i);
+        }
+
+        let mut faction_index = HashMap::new();
+        for (i, faction) in raws.faction_table.iter().enumerate() {
+            faction_index.insert(faction.name.clone(), i);
+        }
+
+        RawMaster {
+            raws,
+            monster_index,
+            item_index,
+            trap_index,
+            faction_index,
+        }
+    }
+
+    pub fn monster(&self, name: &str) -> Option<&MonsterRaw> {
+        self.monster_index
+            .get(name)
+            .map(|idx| &self.raws.monsters[*idx])
+    }
+
+    pub fn item(&self, name: &str) -> Option<&ItemRaw> {
+        self.item_index.get(name).map(|idx| &self.raws.items[*idx])
+    }
+
+    pub fn trap(&self, name: &str) -> Option<&TrapRaw> {
+        self.trap_index.get(name).map(|idx| &self.raws.traps[*idx])
+    }
+
+    pub fn monsters(&self) -> &[MonsterRaw] {
+        &self.raws.monsters
+    }
+
+    pub fn items(&self) -> &[ItemRaw] {
+        &self.raws.items
+    }
+
+    pub fn traps(&self) -> &[TrapRaw] {
+        &self.raws.traps
+    }
+
+    /// How members of faction `mine` react to a member of faction `theirs`.
+    /// Falls back on the "Default" response, and ignores them if there's none.
+    pub fn faction_reaction(&self, mine: &str, theirs: &str) -> Reaction {
+        self.faction_index
+            .get(mine)
+            .map(|idx| &self.raws.faction_table[*idx].responses)
+            .and_then(|responses| {
+                responses
+                    .get(theirs)
+                    .or_else(|| responses.get(DEFAULT_RESPONSE))
+            })
+            .copied()
+            .unwrap_or(Reaction::Ignore)
+    }
+
+    /// Name of the map builder the raws ask for at `depth`, if any.
+    pub fn level_builder_name(&self, depth: i32) -> Option<&str> {
+        self.level(depth).and_then(|level| level.builder.as_deref())
+    }
+
+    /// Width and height of the map at `depth`.
+    pub fn level_dimensions(&self, depth: i32) -> (i32, i32) {
+        let level = self.level(depth);
+        (
+            level
+                .and_then(|level| level.width)
+                .unwrap_or(DEFAULT_MAP_WIDTH),
+            level
+                .and_then(|level| level.height)
+                .unwrap_or(DEFAULT_MAP_HEIGHT),
+        )
+    }
+
+    fn level(&self, depth: i32) -> Option<&LevelRaw> {
+        self.raws.levels.iter().find(|level| level.depth == depth)
+    }
+}
+
+/// Parses the spawn definitions, preferring the file on disk over the embedded copy.
+pub fn load_raws() -> Result<RawMaster, String> {
+    // Named in errors, so it's clear which copy needs fixing
+    let (source, data) = match fs::read_to_string(RAW_PATH) {
+        Ok(data) => (RAW_PATH, data),
+        Err(_) => ("the embedded raws", EMBEDDED_RAWS.to_string()),
+    };
+    parse_raws(source, &data)
+}
+
+/// Reads the definitions in `data` and checks they make sense. `source` names
+/// where they came from in errors.
+pub fn parse_raws(source: &str, data: &str) -> Result<RawMaster, String> {
+    let raws: Raws =
+        serde_json::from_str(data).map_err(|e| format!("Unable to parse {}: {}", source, e))?;
+
+    // The builders need some room to work with
+    for level in raws.levels.iter() {
+        let too_small = |size: Option<i32>| size.is_some_and(|size| size < MIN_MAP_SIZE);
+        if too_small(level.width) || too_small(level.height) {
+            return Err(format!(
+                "Level {} in {} is smaller than {}x{}",
+                level.depth, source, MIN_MAP_SIZE, MIN_MAP_SIZE
+            ));
+        }
+        if let Some(builder) = &level.builder {
+            if !BUILDER_NAMES.contains(&builder.as_str()) {
+                return Err(format!(
+                    "Level {} in {} asks for unknown builder {}",
+                    level.depth, source, builder
+                ));
+            }
+        }
+    }
+
+    let master = RawMaster::new(raws);
+    if !master.faction_index.contains_key(spawner::PLAYER_FACTION) {
+        return Err(format!(
+            "Player in {} belongs to unknown faction {}",
+            source,
+            spawner::PLAYER_FACTION
+        ));
+    }
+    for monster in master.monsters() {
+        if !master.faction_index.contains_key(&monster.faction) {
+            return Err(format!(
+                "{} in {} belongs to unknown faction {}",
+                monster.name, source, monster.faction
+            ));
+        }
+    }
+    // A misspelt faction would quietly get the default reaction instead
+    for faction in master.raws.faction_table.iter() {
+        for other in faction.responses.keys() {
+            if other != DEFAULT_RESPONSE && !master.faction_index.contains_key(other) {
+                return Err(format!(
+                    "{} in {} reacts to unknown faction {}",
+                    faction.name, source, other
+                ));
+            }
+        }
+    }
+
+    // Colors are only read when something spawns, which could be deep into a run
+    let renderables = master
+        .monsters()
+        .iter()
+        .map(|monster| (&monster.name, &monster.renderable))
+        .chain(
+            master
+                .items()
+                .iter()
+                .map(|item| (&item.name, &item.renderable)),
+        )
+        .chain(
+            master
+                .traps()
+                .iter()
+                .map(|trap| (&trap.name, &trap.renderable)),
+        );
+    for (name, renderable) in renderables {
+        for color in [&renderable.fg, &renderable.bg].iter() {
+            if RGB::from_hex(color).is_err() {
+                return Err(format!(
+                    "{} in {} has invalid color {}",
+                    name, source, color
+                ));
+            }
+        }
+    }
+
+    Ok(master)
+}
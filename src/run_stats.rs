@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+/// Tallies for the current run, shown on the game over screen.
+#[derive(Default, Serialize, Deserialize, Clone)]
+pub struct RunStats {
+    pub turns: i32,
+    pub kills: i32,
+    // Total hp the player has taken off other creatures, by any means
+    pub damage_dealt: i32,
+    // Name of whatever last hurt the player, reported as the cause of death
+    pub last_hit_by: Option<String>,
+}
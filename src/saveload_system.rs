@@ -0,0 +1,313 @@
+use super::components::*;
+use super::gamelog::GameLog;
+use super::map::Map;
+use super::register_components;
+use super::run_stats::RunStats;
+use rltk::RandomNumberGenerator;
+use serde::{Deserialize, Serialize};
+use specs::error::NoError;
+use specs::prelude::*;
+use specs::saveload::{
+    DeserializeComponents, MarkedBuilder, SerializeComponents, SimpleMarker, SimpleMarkerAllocator,
+};
+use std::fs;
+use std::fs::File;
+use std::path::Path;
+
+/// Where the game is saved to and continued from.
+pub const SAVE_PATH: &str = "./savegame.json";
+
+/// Bump this whenever the layout of a saved component or of the map changes,
+/// so old save files are rejected instead of loaded into garbage.
+pub const SAVE_VERSION: u32 = 15;
+
+// First value written to every save file
+#[derive(Serialize, Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+macro_rules! serialize_individually {
+    ($ecs:expr, $ser:expr, $data:expr, $( $type:ty),*) => {
+        $(
+        SerializeComponents::<NoError, SimpleMarker<SerializeMe>>::serialize(
+            &( $ecs.read_storage::<$type>(), ),
+            &$data.0,
+            &$data.1,
+            &mut $ser,
+        )
+        .map_err(|e| format!("Unable to serialize component: {}", e))?;
+        )*
+    };
+}
+
+macro_rules! deserialize_individually {
+    ($ecs:expr, $de:expr, $data:expr, $( $type:ty),*) => {
+        $(
+        DeserializeComponents::<NoError, _>::deserialize(
+            &mut ( &mut $ecs.write_storage::<$type>(), ),
+            &mut $data.0, // entities
+            &mut $data.1, // marker
+            &mut $data.2, // allocator
+            &mut $de,
+        )
+        .map_err(|e| format!("Unable to deserialize component: {}", e))?;
+        )*
+    };
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save_game(_ecs: &mut World, _path: &str) -> Result<(), String> {
+    Ok(())
+}
+
+/// Writes the world to `path`. The world itself is left as it was, even if the
+/// save couldn't be written.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_game(ecs: &mut World, path: &str) -> Result<(), String> {
+    // Create a helper entity holding the state that doesn't live in components
+    let map_copy = (*ecs.fetch::<Map>()).clone();
+    let log_copy = ecs.fetch::<GameLog>().entries.clone();
+    let stats_copy = (*ecs.fetch::<RunStats>()).clone();
+    // The generator's state can't be saved, so the loaded game is reseeded from a
+    // roll taken here instead. Loading the same save then always plays out the same.
+    let rng_seed = ecs.write_resource::<RandomNumberGenerator>().rand::<u64>();
+    let save_helper = ecs
+        .create_entity()
+        .with(SerializationHelper {
+            map: map_copy,
+            log: log_copy,
+            stats: stats_copy,
+            rng_seed,
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+
+    // Actually serialize
+    let result = write_save(ecs, path);
+
+    // Clean up
+    ecs.delete_entity(save_helper)
+        .expect("@ERROR: Unable to delete save helper");
+
+    result
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_save(ecs: &World, path: &str) -> Result<(), String> {
+    {
+        let data = (
+            ecs.entities(),
+            ecs.read_storage::<SimpleMarker<SerializeMe>>(),
+        );
+
+        let writer = File::create(path).map_err(|e| e.to_string())?;
+        let mut serializer = serde_json::Serializer::new(writer);
+        SaveHeader {
+            version: SAVE_VERSION,
+        }
+        .serialize(&mut serializer)
+        .map_err(|e| e.to_string())?;
+
+        serialize_individually!(
+            ecs,
+            serializer,
+            data,
+            Position,
+            Renderable,
+            Player,
+            Viewshed,
+            Monster,
+            Name,
+            BlocksTile,
+            CombatStats,
+            WantsToMelee,
+            Item,
+            InBackpack,
+            WantsToPickupItem,
+            Consumable,
+            ProvidesHealing,
+            WantsToUseItem,
+            WantsToDropItem,
+            Ranged,
+            InflictsDamage,
+            AreaOfEffect,
+            Confusion,
+            Equippable,
+            Equipped,
+            MeleePowerBonus,
+            DefenseBonus,
+            WantsToRemoveItem,
+            Energy,
+            HungerClock,
+            ProvidesFood,
+            Experience,
+            XpValue,
+            Door,
+            BlocksVisibility,
+            WantsToOpenDoor,
+            Hidden,
+            EntryTrigger,
+            SingleActivation,
+            TeleportsTarget,
+            SoundsAlarm,
+            AiBehaviour,
+            MonsterAiState,
+            Faction,
+            Provoked,
+            SerializationHelper
+        );
+    }
+
+    Ok(())
+}
+
+pub fn does_save_exist(path: &str) -> bool {
+    Path::new(path).exists()
+}
+
+pub fn delete_save(path: &str) -> Result<(), String> {
+    if does_save_exist(path) {
+        fs::remove_file(path).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Replaces the contents of the world with the game saved at `path`.
+/// The world is left untouched if the save can't be read or is from another version.
+pub fn load_game(ecs: &mut World, path: &str) -> Result<(), String> {
+    let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+    // Read it into a scratch world first, so a broken save is caught before
+    // anything in this one is deleted
+    let mut scratch = World::new();
+    register_components(&mut scratch);
+    scratch.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+    read_save(&mut scratch, &data)?;
+
+    // Delete everything
+    {
+        let mut to_delete = Vec::new();
+        for e in ecs.entities().join() {
+            to_delete.push(e);
+        }
+        for del in to_delete.iter() {
+            ecs.delete_entity(*del)
+                .expect("@ERROR: Unable to delete entity");
+        }
+    }
+
+    read_save(ecs, &data)?;
+
+    let mut delete_me: Option<Entity> = None;
+    {
+        let entities = ecs.entities();
+        let helper = ecs.read_storage::<SerializationHelper>();
+        let player = ecs.read_storage::<Player>();
+        let position = ecs.read_storage::<Position>();
+
+        // Restore map, log, stats & rng resources from the helper
+        for (e, h) in (&entities, &helper).join() {
+            let mut world_map = ecs.write_resource::<Map>();
+            *world_map = h.map.clone();
+            world_map.tile_content = vec![Vec::new(); world_map.tiles.len()];
+            world_map.view_blocked = vec![false; world_map.tiles.len()];
+
+            let mut log = ecs.write_resource::<GameLog>();
+            log.entries = h.log.clone();
+
+            let mut stats = ecs.write_resource::<RunStats>();
+            *stats = h.stats.clone();
+
+            let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+            *rng = RandomNumberGenerator::seeded(h.rng_seed);
+
+            delete_me = Some(e);
+        }
+
+        // Restore the player entity & position resources
+        for (e, _p, pos) in (&entities, &player, &position).join() {
+            let mut player_pos = ecs.write_resource::<rltk::Point>();
+            *player_pos = rltk::Point::new(pos.x, pos.y);
+            let mut player_resource = ecs.write_resource::<Entity>();
+            *player_resource = e;
+        }
+    }
+
+    if let Some(helper) = delete_me {
+        ecs.delete_entity(helper)
+            .expect("@ERROR: Unable to delete save helper");
+    }
+
+    Ok(())
+}
+
+// Checks the save's version, then reads every component in it into `ecs`
+fn read_save(ecs: &mut World, data: &str) -> Result<(), String> {
+    let mut de = serde_json::Deserializer::from_str(data);
+
+    let header = SaveHeader::deserialize(&mut de).map_err(|e| e.to_string())?;
+    if header.version != SAVE_VERSION {
+        return Err(format!(
+            "Save file version {} is not supported (expected {})",
+            header.version, SAVE_VERSION
+        ));
+    }
+
+    let mut d = (
+        &mut ecs.entities(),
+        &mut ecs.write_storage::<SimpleMarker<SerializeMe>>(),
+        &mut ecs.write_resource::<SimpleMarkerAllocator<SerializeMe>>(),
+    );
+
+    deserialize_individually!(
+        ecs,
+        de,
+        d,
+        Position,
+        Renderable,
+        Player,
+        Viewshed,
+        Monster,
+        Name,
+        BlocksTile,
+        CombatStats,
+        WantsToMelee,
+        Item,
+        InBackpack,
+        WantsToPickupItem,
+        Consumable,
+        ProvidesHealing,
+        WantsToUseItem,
+        WantsToDropItem,
+        Ranged,
+        InflictsDamage,
+        AreaOfEffect,
+        Confusion,
+        Equippable,
+        Equipped,
+        MeleePowerBonus,
+        DefenseBonus,
+        WantsToRemoveItem,
+        Energy,
+        HungerClock,
+        ProvidesFood,
+        Experience,
+        XpValue,
+        Door,
+        BlocksVisibility,
+        WantsToOpenDoor,
+        Hidden,
+        EntryTrigger,
+        SingleActivation,
+        TeleportsTarget,
+        SoundsAlarm,
+        AiBehaviour,
+        MonsterAiState,
+        Faction,
+        Provoked,
+        SerializationHelper
+    );
+
+    Ok(())
+}
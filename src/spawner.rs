@@ -0,0 +1,311 @@
+use super::hunger_system::HUNGER_STATE_DURATION;
+use super::initiative_system::NORMAL_SPEED;
+use super::random_table::RandomTable;
+use super::raws::{RawMaster, RenderableRaw};
+use super::{
+    AiBehaviour, AiState, AreaOfEffect, BlocksTile, BlocksVisibility, CombatStats, Confusion,
+    Consumable, DefenseBonus, Door, Energy, EntryTrigger, Equippable, Experience, Faction, Hidden,
+    HungerClock, HungerState, InflictsDamage, Item, Map, MeleePowerBonus, Monster, MonsterAiState,
+    Name, Player, Position, ProvidesFood, ProvidesHealing, Ranged, Rect, Renderable, SerializeMe,
+    SingleActivation, SoundsAlarm, TeleportsTarget, TileType, Viewshed, XpValue,
+};
+use rltk::{RandomNumberGenerator, RGB};
+use specs::prelude::*;
+use specs::saveload::{MarkedBuilder, SimpleMarker};
+
+/// The player's faction; monsters in it are their allies.
+pub const PLAYER_FACTION: &str = "Player";
+
+/// Spawns the player and returns their entity object.
+pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position {
+            x: player_x,
+            y: player_y,
+        })
+        .with(Renderable {
+            glyph: rltk::to_cp437('@'),
+            fg: RGB::named(rltk::YELLOW),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 0,
+        })
+        .with(Player {})
+        .with(Viewshed {
+            visible_tiles: Vec::new(),
+            range: 8,
+            dirty: true,
+        })
+        .with(Name {
+            name: "Player".to_string(),
+        })
+        .with(CombatStats {
+            max_hp: 30,
+            hp: 30,
+            defense: 2,
+            power: 5,
+        })
+        .with(Energy {
+            speed: NORMAL_SPEED,
+            energy: 0,
+        })
+        .with(HungerClock {
+            state: HungerState::WellFed,
+            duration: HUNGER_STATE_DURATION,
+        })
+        .with(Experience { level: 1, xp: 0 })
+        .with(Faction {
+            name: PLAYER_FACTION.to_string(),
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+/// Builds a renderable from its raw definition.
+fn renderable_from_raw(raw: &RenderableRaw) -> Renderable {
+    Renderable {
+        glyph: rltk::to_cp437(raw.glyph.chars().next().unwrap_or('?')),
+        fg: RGB::from_hex(&raw.fg).expect("@ERROR: Invalid foreground color in raws"),
+        bg: RGB::from_hex(&raw.bg).expect("@ERROR: Invalid background color in raws"),
+        render_order: raw.order,
+    }
+}
+
+/// Most things a room can hold at depth 1; deeper rooms get one more per level.
+const MAX_SPAWNS: i32 = 4;
+
+/// Weighted table of every monster & item allowed at `depth`.
+fn spawn_table(raws: &RawMaster, depth: i32) -> RandomTable {
+    let mut table = RandomTable::new();
+    for monster in raws.monsters().iter() {
+        if depth >= monster.min_depth && depth <= monster.max_depth {
+            table = table.add(&monster.name, monster.spawn_weight);
+        }
+    }
+    for item in raws.items().iter() {
+        if depth >= item.min_depth && depth <= item.max_depth {
+            table = table.add(&item.name, item.spawn_weight);
+        }
+    }
+    for trap in raws.traps().iter() {
+        if depth >= trap.min_depth && depth <= trap.max_depth {
+            table = table.add(&trap.name, trap.spawn_weight);
+        }
+    }
+    table
+}
+
+/// Spawns a closed door.
+pub fn door(ecs: &mut World, x: i32, y: i32) {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('+'),
+            fg: RGB::named(rltk::CHOCOLATE),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Door".to_string(),
+        })
+        .with(Door { open: false })
+        .with(BlocksVisibility {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+}
+
+/// Fills a room with monsters & items rolled from the spawn table for the map's depth,
+/// each on its own random floor tile inside the room.
+pub fn spawn_room(ecs: &mut World, map: &Map, room: &Rect) {
+    let mut possible_targets: Vec<usize> = Vec::new();
+    for y in room.y1 + 1..=room.y2 {
+        for x in room.x1 + 1..=room.x2 {
+            possible_targets.push(map.xy_idx(x, y));
+        }
+    }
+
+    spawn_region(ecs, map, &possible_targets);
+}
+
+/// Fills an arbitrary set of map tiles the same way as a room, for maps that
+/// don't have rooms. Only floor tiles are used, so the stairs are left clear.
+pub fn spawn_region(ecs: &mut World, map: &Map, area: &[usize]) {
+    let mut spawn_points: Vec<(String, usize)> = Vec::new();
+
+    // Scope to keep the borrow checker happy
+    {
+        let raws = ecs.fetch::<RawMaster>();
+        let table = spawn_table(&raws, map.depth);
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+
+        // The budget grows with depth, and can come out at zero or less for an empty area
+        let num_spawns = rng.roll_dice(1, MAX_SPAWNS + 3) + (map.depth - 1) - 3;
+
+        let mut free_tiles: Vec<usize> = area
+            .iter()
+            .filter(|idx| map.tiles[**idx] == TileType::Floor)
+            .cloned()
+            .collect();
+
+        for _i in 0..num_spawns {
+            if free_tiles.is_empty() {
+                break;
+            }
+
+            let pick = (rng.roll_dice(1, free_tiles.len() as i32) - 1) as usize;
+            let idx = free_tiles.remove(pick);
+            if let Some(name) = table.roll(&mut rng) {
+                spawn_points.push((name, idx));
+            }
+        }
+    }
+
+    // Actually spawn the things
+    for (name, idx) in spawn_points.iter() {
+        let x = *idx as i32 % map.width;
+        let y = *idx as i32 / map.width;
+        spawn_named(ecs, name, x, y);
+    }
+}
+
+/// Spawns the monster, item or trap called `name` in the raws, in that order of preference.
+pub fn spawn_named(ecs: &mut World, name: &str, x: i32, y: i32) -> Option<Entity> {
+    spawn_named_monster(ecs, name, x, y)
+        .or_else(|| spawn_named_item(ecs, name, x, y))
+        .or_else(|| spawn_named_trap(ecs, name, x, y))
+}
+
+/// Spawns the monster called `name` in the raws, if there is one.
+pub fn spawn_named_monster(ecs: &mut World, name: &str, x: i32, y: i32) -> Option<Entity> {
+    let raw = ecs.fetch::<RawMaster>().monster(name).cloned()?;
+
+    let mut builder = ecs
+        .create_entity()
+        .with(Position { x, y })
+        .with(renderable_from_raw(&raw.renderable))
+        .with(Viewshed {
+            visible_tiles: Vec::new(),
+            range: raw.vision_range,
+            dirty: true,
+        })
+        .with(Monster {})
+        .with(Name {
+            name: raw.name.clone(),
+        })
+        .with(BlocksTile {})
+        .with(CombatStats {
+            max_hp: raw.stats.max_hp,
+            hp: raw.stats.max_hp,
+            defense: raw.stats.defense,
+            power: raw.stats.power,
+        })
+        .with(Energy {
+            speed: raw.speed.unwrap_or(NORMAL_SPEED),
+            energy: 0,
+        })
+        .with(AiBehaviour {
+            wanders: raw.ai.wanders,
+            flee_below: raw.ai.flee_below,
+            search_turns: raw.ai.search_turns,
+        })
+        .with(MonsterAiState {
+            state: AiState::Idle,
+        })
+        .with(Faction {
+            name: raw.faction.clone(),
+        });
+
+    if raw.xp > 0 {
+        builder = builder.with(XpValue { amount: raw.xp });
+    }
+
+    Some(builder.marked::<SimpleMarker<SerializeMe>>().build())
+}
+
+/// Spawns the item called `name` in the raws, if there is one.
+/// Only the components the definition asks for are added.
+pub fn spawn_named_item(ecs: &mut World, name: &str, x: i32, y: i32) -> Option<Entity> {
+    let raw = ecs.fetch::<RawMaster>().item(name).cloned()?;
+
+    let mut builder = ecs
+        .create_entity()
+        .with(Position { x, y })
+        .with(renderable_from_raw(&raw.renderable))
+        .with(Name {
+            name: raw.name.clone(),
+        })
+        .with(Item {});
+
+    if raw.consumable {
+        builder = builder.with(Consumable {});
+    }
+
+    let effects = &raw.effects;
+    if let Some(heal_amount) = effects.healing {
+        builder = builder.with(ProvidesHealing { heal_amount });
+    }
+    if let Some(damage) = effects.damage {
+        builder = builder.with(InflictsDamage { damage });
+    }
+    if let Some(range) = effects.range {
+        builder = builder.with(Ranged { range });
+    }
+    if let Some(radius) = effects.area_of_effect {
+        builder = builder.with(AreaOfEffect { radius });
+    }
+    if let Some(turns) = effects.confusion {
+        builder = builder.with(Confusion { turns });
+    }
+    if effects.food {
+        builder = builder.with(ProvidesFood {});
+    }
+
+    if let Some(equippable) = &raw.equippable {
+        builder = builder.with(Equippable {
+            slot: equippable.slot,
+        });
+        if equippable.power_bonus != 0 {
+            builder = builder.with(MeleePowerBonus {
+                power: equippable.power_bonus,
+            });
+        }
+        if equippable.defense_bonus != 0 {
+            builder = builder.with(DefenseBonus {
+                defense: equippable.defense_bonus,
+            });
+        }
+    }
+
+    Some(builder.marked::<SimpleMarker<SerializeMe>>().build())
+}
+
+/// Spawns the trap called `name` in the raws, if there is one. Traps start out hidden.
+pub fn spawn_named_trap(ecs: &mut World, name: &str, x: i32, y: i32) -> Option<Entity> {
+    let raw = ecs.fetch::<RawMaster>().trap(name).cloned()?;
+
+    let mut builder = ecs
+        .create_entity()
+        .with(Position { x, y })
+        .with(renderable_from_raw(&raw.renderable))
+        .with(Name {
+            name: raw.name.clone(),
+        })
+        .with(Hidden {})
+        .with(EntryTrigger {});
+
+    if raw.single_activation {
+        builder = builder.with(SingleActivation {});
+    }
+
+    let effects = &raw.effects;
+    if let Some(damage) = effects.damage {
+        builder = builder.with(InflictsDamage { damage });
+    }
+    if effects.teleport {
+        builder = builder.with(TeleportsTarget {});
+    }
+    if let Some(radius) = effects.alarm {
+        builder = builder.with(SoundsAlarm { radius });
+    }
+
+    Some(builder.marked::<SimpleMarker<SerializeMe>>().build())
+}
@@ -0,0 +1,193 @@
+use super::monster_ai_system;
+use super::raws::{RawMaster, Reaction};
+use super::{
+    gamelog::GameLog, run_stats::RunStats, AiState, Door, EntityMoved, EntryTrigger, Faction,
+    Hidden, InflictsDamage, Map, Monster, MonsterAiState, Name, Position, Provoked,
+    SingleActivation, SoundsAlarm, SufferDamage, TeleportsTarget, TileType, Viewshed,
+};
+use rltk::{Point, RandomNumberGenerator};
+use specs::prelude::*;
+
+/// How long monsters spend heading for an alarm before losing interest.
+const ALARM_SEARCH_TURNS: i32 = 30;
+
+/// Sets off the traps under anything that moved this turn.
+pub struct TriggerSystem {}
+
+impl<'a> System<'a> for TriggerSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Map>,
+        ReadExpect<'a, Entity>, // Player entity resource
+        WriteExpect<'a, Point>, // Player position resource
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, RunStats>,
+        WriteExpect<'a, RandomNumberGenerator>,
+        ReadExpect<'a, RawMaster>,
+        Entities<'a>,
+        WriteStorage<'a, EntityMoved>,
+        WriteStorage<'a, Position>,
+        ReadStorage<'a, EntryTrigger>,
+        WriteStorage<'a, Hidden>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, InflictsDamage>,
+        WriteStorage<'a, SufferDamage>,
+        ReadStorage<'a, TeleportsTarget>,
+        ReadStorage<'a, SoundsAlarm>,
+        ReadStorage<'a, SingleActivation>,
+        ReadStorage<'a, Monster>,
+        WriteStorage<'a, MonsterAiState>,
+        WriteStorage<'a, Viewshed>,
+        ReadStorage<'a, Door>,
+        ReadStorage<'a, Faction>,
+        ReadStorage<'a, Provoked>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            map,
+            player_entity,
+            mut player_pos,
+            mut log,
+            mut run_stats,
+            mut rng,
+            raws,
+            entities,
+            mut moved,
+            mut positions,
+            entry_triggers,
+            mut hidden,
+            names,
+            inflicts_damage,
+            mut inflict_damage,
+            teleporters,
+            alarms,
+            single_activations,
+            monsters,
+            mut ai_states,
+            mut viewsheds,
+            doors,
+            factions,
+            provoked,
+        ) = data;
+
+        // Who stepped on what; collected first since teleporting moves people around
+        let mut triggered: Vec<(Entity, Entity)> = Vec::new();
+        for (entity, _moved, pos) in (&entities, &moved, &positions).join() {
+            let idx = map.xy_idx(pos.x, pos.y);
+            for trap in map.tile_content[idx].iter() {
+                if *trap != entity && entry_triggers.get(*trap).is_some() {
+                    triggered.push((entity, *trap));
+                }
+            }
+        }
+        moved.clear();
+
+        let mut disarmed: Vec<Entity> = Vec::new();
+        for (victim, trap) in triggered {
+            if disarmed.contains(&trap) {
+                continue;
+            }
+
+            let is_player = victim == *player_entity;
+            let trap_name = names
+                .get(trap)
+                .map_or("trap".to_string(), |name| name.name.clone());
+            let trap_pos = match positions.get(trap) {
+                Some(pos) => Point::new(pos.x, pos.y),
+                None => continue,
+            };
+
+            // Going off gives it away
+            hidden.remove(trap);
+            if is_player {
+                log.entries
+                    .insert(0, format!("You set off a {}!", trap_name));
+            } else if map.visible_tiles[map.xy_idx(trap_pos.x, trap_pos.y)] {
+                let victim_name = names
+                    .get(victim)
+                    .map_or("Something".to_string(), |name| name.name.clone());
+                log.entries
+                    .insert(0, format!("{} sets off a {}!", victim_name, trap_name));
+            }
+
+            if let Some(damage) = inflicts_damage.get(trap) {
+                SufferDamage::new_damage(&mut inflict_damage, victim, damage.damage, None);
+                if is_player {
+                    run_stats.last_hit_by = Some(format!("a {}", trap_name));
+                }
+            }
+
+            if let Some(alarm) = alarms.get(trap) {
+                log.entries.insert(0, "An alarm blares!".to_string());
+                let player_faction = factions.get(*player_entity);
+                for (monster, _monster, pos, ai, faction) in
+                    (&entities, &monsters, &positions, &mut ai_states, &factions).join()
+                {
+                    let distance = rltk::DistanceAlg::Pythagoras
+                        .distance2d(trap_pos, Point::new(pos.x, pos.y));
+                    // Anything already chasing (or running from) the player has better things to do
+                    let busy = matches!(ai.state, AiState::Chase { .. } | AiState::Flee);
+                    // Only those out to get the player come looking; allies stay put
+                    let hostile = player_faction.is_some_and(|player_faction| {
+                        monster_ai_system::reaction(
+                            &raws,
+                            faction,
+                            provoked.get(monster),
+                            *player_entity,
+                            player_faction,
+                        ) == Reaction::Attack
+                    });
+                    if distance <= alarm.radius as f32 && !busy && hostile {
+                        ai.state = AiState::Search {
+                            x: trap_pos.x,
+                            y: trap_pos.y,
+                            turns: ALARM_SEARCH_TURNS,
+                        };
+                    }
+                }
+            }
+
+            if teleporters.get(trap).is_some() {
+                let trap_idx = map.xy_idx(trap_pos.x, trap_pos.y);
+                // Doors don't block movement, but nobody should end up stuck inside a closed one
+                let closed_door_at = |idx: usize| {
+                    map.tile_content[idx]
+                        .iter()
+                        .any(|e| doors.get(*e).is_some_and(|door| !door.open))
+                };
+                let free_tiles: Vec<usize> = (0..map.tiles.len())
+                    .filter(|idx| {
+                        *idx != trap_idx
+                            && map.tiles[*idx] == TileType::Floor
+                            && !map.blocked[*idx]
+                            && !closed_door_at(*idx)
+                    })
+                    .collect();
+                if !free_tiles.is_empty() {
+                    let idx = free_tiles[(rng.roll_dice(1, free_tiles.len() as i32) - 1) as usize];
+                    let (x, y) = (idx as i32 % map.width, idx as i32 / map.width);
+                    if let Some(pos) = positions.get_mut(victim) {
+                        pos.x = x;
+                        pos.y = y;
+                    }
+                    if let Some(viewshed) = viewsheds.get_mut(victim) {
+                        viewshed.dirty = true;
+                    }
+                    if is_player {
+                        *player_pos = Point::new(x, y);
+                        log.entries
+                            .insert(0, "You are teleported away!".to_string());
+                    }
+                }
+            }
+
+            if single_activations.get(trap).is_some() {
+                disarmed.push(trap);
+                entities
+                    .delete(trap)
+                    .expect("@ERROR: Unable to delete trap");
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use common::TestWorld;
+use rltk::Point;
+use rogue_like::camera::{self, VIEW_HEIGHT, VIEW_WIDTH};
+use rogue_like::{player, RunState};
+
+const ROOM: [&str; 3] = ["#####", "#@..#", "#####"];
+
+#[test]
+fn player_is_drawn_in_the_middle_of_the_view() {
+    let world = TestWorld::new(&ROOM);
+
+    assert_eq!(
+        camera::world_to_screen(&world.ecs, Point::new(1, 1)),
+        Some(Point::new(VIEW_WIDTH / 2, VIEW_HEIGHT / 2))
+    );
+}
+
+#[test]
+fn screen_and_world_positions_convert_both_ways() {
+    let world = TestWorld::new(&ROOM);
+
+    let map_pos = camera::screen_to_world(&world.ecs, 10, 5);
+    assert_eq!(
+        camera::world_to_screen(&world.ecs, map_pos),
+        Some(Point::new(10, 5))
+    );
+}
+
+#[test]
+fn positions_outside_the_view_are_not_drawn() {
+    let world = TestWorld::new(&ROOM);
+
+    assert_eq!(
+        camera::world_to_screen(&world.ecs, Point::new(1 + VIEW_WIDTH, 1)),
+        None
+    );
+}
+
+#[test]
+fn player_can_walk_past_the_edge_of_the_screen_on_a_wide_map() {
+    let mut row = String::from("#");
+    row.push_str(&".".repeat(77));
+    row.push('@');
+    row.push_str(&".".repeat(20));
+    row.push('#');
+    let wall = "#".repeat(row.len());
+    let mut world = TestWorld::new(&[&wall, &row, &wall]);
+    world.run_systems(RunState::PreRun);
+
+    assert_eq!(world.map().width, 100);
+    player::try_move_player(1, 0, &mut world.ecs);
+    player::try_move_player(1, 0, &mut world.ecs);
+    assert_eq!(world.position(world.player), (80, 1));
+}
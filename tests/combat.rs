@@ -0,0 +1,105 @@
+mod common;
+
+use common::TestWorld;
+use rogue_like::{CombatStats, Name, RunState};
+use specs::prelude::*;
+
+const CORRIDOR: [&str; 3] = ["########", "#@.....#", "########"];
+
+#[test]
+fn player_attack_deals_power_minus_defense() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    // Player power is 5
+    let goblin = world.spawn_monster("Goblin", 2, 1, 16, 1, 4);
+    world.run_systems(RunState::PreRun);
+
+    world.attack(world.player, goblin);
+    world.run_systems(RunState::PlayerTurn);
+
+    assert_eq!(world.hp(goblin), 12);
+    assert_eq!(world.log()[0], "Player hits Goblin, for 4 hp");
+    assert_eq!(world.run_stats().damage_dealt, 4);
+}
+
+#[test]
+fn armor_stronger_than_the_attack_blocks_it() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let golem = world.spawn_monster("Golem", 2, 1, 16, 10, 4);
+    world.run_systems(RunState::PreRun);
+
+    world.attack(world.player, golem);
+    world.run_systems(RunState::PlayerTurn);
+
+    assert_eq!(world.hp(golem), 16);
+    assert_eq!(world.log()[0], "Player is unable to hurt Golem");
+}
+
+#[test]
+fn killing_blow_removes_the_monster_and_counts_a_kill() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let goblin = world.spawn_monster("Goblin", 2, 1, 3, 0, 4);
+    world.run_systems(RunState::PreRun);
+
+    world.attack(world.player, goblin);
+    world.run_systems(RunState::PlayerTurn);
+
+    assert!(!world.ecs.is_alive(goblin));
+    assert_eq!(world.log()[0], "Goblin is dead");
+    assert_eq!(world.run_stats().kills, 1);
+}
+
+#[test]
+fn several_attackers_all_land_their_hits() {
+    let mut world = TestWorld::new(&["#####", "#...#", "#.@.#", "#...#", "#####"]);
+    // Player defense is 2
+    world.spawn_monster("Goblin", 1, 2, 16, 1, 4);
+    world.spawn_monster("Orc", 3, 2, 16, 1, 5);
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+
+    assert_eq!(world.hp(world.player), 30 - 2 - 3);
+}
+
+#[test]
+fn player_death_ends_the_game() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    world.spawn_monster("Dragon", 2, 1, 100, 0, 100);
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+
+    assert!(world.runstate() == RunState::GameOver);
+    assert_eq!(world.run_stats().last_hit_by, Some("Dragon".to_string()));
+    // The player is kept around for the game over screen
+    assert!(world.ecs.is_alive(world.player));
+}
+
+#[test]
+fn attacking_something_without_a_name_still_gets_logged() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let goblin = world.spawn_monster("Goblin", 2, 1, 16, 1, 4);
+    world.run_systems(RunState::PreRun);
+    world.ecs.write_storage::<Name>().remove(goblin);
+
+    world.attack(world.player, goblin);
+    world.run_systems(RunState::PlayerTurn);
+
+    assert_eq!(world.hp(goblin), 12);
+    assert_eq!(world.log()[0], "Player hits something, for 4 hp");
+}
+
+#[test]
+fn attacking_something_without_combat_stats_does_nothing() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let goblin = world.spawn_monster("Goblin", 2, 1, 16, 1, 4);
+    world.run_systems(RunState::PreRun);
+    world.ecs.write_storage::<CombatStats>().remove(goblin);
+    let log_length = world.log().len();
+
+    world.attack(world.player, goblin);
+    world.run_systems(RunState::PlayerTurn);
+
+    assert_eq!(world.log().len(), log_length);
+    assert_eq!(world.run_stats().damage_dealt, 0);
+}
@@ -0,0 +1,225 @@
+// Each test binary only uses part of the harness
+#![allow(dead_code)]
+
+use rltk::{Point, RandomNumberGenerator};
+use rogue_like::damage_system;
+use rogue_like::gamelog::GameLog;
+use rogue_like::initiative_system::NORMAL_SPEED;
+use rogue_like::run_stats::RunStats;
+use rogue_like::{
+    spawner, AiBehaviour, AiState, BlocksTile, CombatStats, Energy, Faction, Map, Monster,
+    MonsterAiState, Name, Position, RunState, State, TileType, Viewshed, WantsToMelee,
+    DEFAULT_MAP_HEIGHT, DEFAULT_MAP_WIDTH,
+};
+use specs::prelude::*;
+
+/// Makes a depth 1 map from rows of text: `#` wall, `.` floor, `>` stairs down.
+/// `@` marks floor too (see `TestWorld::new`), as does `+` for where a test puts a door.
+/// Anything off the drawing is wall.
+/// The map is the default size, or bigger if the drawing needs it.
+pub fn map_from_rows(rows: &[&str]) -> Map {
+    let width = rows
+        .iter()
+        .map(|row| row.len() as i32)
+        .fold(DEFAULT_MAP_WIDTH, i32::max);
+    let height = i32::max(rows.len() as i32, DEFAULT_MAP_HEIGHT);
+    let mut map = Map::new(1, width, height);
+    for (y, row) in rows.iter().enumerate() {
+        for (x, glyph) in row.chars().enumerate() {
+            let idx = map.xy_idx(x as i32, y as i32);
+            map.tiles[idx] = match glyph {
+                '.' | '@' | '+' => TileType::Floor,
+                '>' => TileType::DownStairs,
+                _ => TileType::Wall,
+            };
+        }
+    }
+    map
+}
+
+/// Faction `TestWorld::spawn_monster` puts monsters in: hostile to the player,
+/// friendly to each other.
+pub const MONSTER_FACTION: &str = "Monsters";
+
+/// A world with every component registered and no console, built around a
+/// hand-drawn map, for stepping the game systems one turn at a time.
+pub struct TestWorld {
+    pub ecs: World,
+    pub player: Entity,
+}
+
+impl TestWorld {
+    /// The player starts on the `@` in `rows`.
+    pub fn new(rows: &[&str]) -> TestWorld {
+        let (player_x, player_y) = rows
+            .iter()
+            .enumerate()
+            .find_map(|(y, row)| row.find('@').map(|x| (x as i32, y as i32)))
+            .expect("The test map has no @ for the player");
+
+        let mut ecs = State::new(false).ecs;
+        ecs.insert(RandomNumberGenerator::seeded(1));
+        ecs.insert(map_from_rows(rows));
+        ecs.insert(GameLog {
+            entries: Vec::new(),
+        });
+        ecs.insert(RunStats::default());
+        ecs.insert(RunState::PreRun);
+
+        let player = spawner::player(&mut ecs, player_x, player_y);
+        ecs.insert(Point::new(player_x, player_y));
+        ecs.insert(player);
+
+        TestWorld { ecs, player }
+    }
+
+    /// Adds a monster with exactly the given stats, independent of the raws.
+    /// It moves at normal speed.
+    pub fn spawn_monster(
+        &mut self,
+        name: &str,
+        x: i32,
+        y: i32,
+        hp: i32,
+        defense: i32,
+        power: i32,
+    ) -> Entity {
+        self.ecs
+            .create_entity()
+            .with(Position { x, y })
+            .with(Viewshed {
+                visible_tiles: Vec::new(),
+                range: 8,
+                dirty: true,
+            })
+            .with(Monster {})
+            .with(Name {
+                name: name.to_string(),
+            })
+            .with(BlocksTile {})
+            .with(CombatStats {
+                max_hp: hp,
+                hp,
+                defense,
+                power,
+            })
+            .with(Energy {
+                speed: NORMAL_SPEED,
+                energy: 0,
+            })
+            .with(AiBehaviour {
+                wanders: false,
+                flee_below: 0,
+                search_turns: 0,
+            })
+            .with(MonsterAiState {
+                state: AiState::Idle,
+            })
+            .with(Faction {
+                name: MONSTER_FACTION.to_string(),
+            })
+            .build()
+    }
+
+    pub fn set_faction(&mut self, entity: Entity, name: &str) {
+        self.ecs
+            .write_storage::<Faction>()
+            .insert(
+                entity,
+                Faction {
+                    name: name.to_string(),
+                },
+            )
+            .expect("Unable to insert faction");
+    }
+
+    pub fn set_behaviour(&mut self, entity: Entity, behaviour: AiBehaviour) {
+        self.ecs
+            .write_storage::<AiBehaviour>()
+            .insert(entity, behaviour)
+            .expect("Unable to insert behaviour");
+    }
+
+    pub fn set_ai_state(&mut self, entity: Entity, state: AiState) {
+        self.ecs
+            .write_storage::<MonsterAiState>()
+            .insert(entity, MonsterAiState { state })
+            .expect("Unable to insert ai state");
+    }
+
+    pub fn ai_state(&self, entity: Entity) -> AiState {
+        self.ecs
+            .read_storage::<MonsterAiState>()
+            .get(entity)
+            .expect("Entity has no ai state")
+            .state
+    }
+
+    pub fn set_speed(&mut self, entity: Entity, speed: i32) {
+        self.ecs
+            .write_storage::<Energy>()
+            .get_mut(entity)
+            .expect("Entity has no energy")
+            .speed = speed;
+    }
+
+    /// Queues a melee attack, as moving into a monster would.
+    pub fn attack(&mut self, attacker: Entity, target: Entity) {
+        self.ecs
+            .write_storage::<WantsToMelee>()
+            .insert(attacker, WantsToMelee { target })
+            .expect("Unable to insert attack");
+    }
+
+    /// Runs one pass of the game systems in `runstate`, then clears out the dead,
+    /// the same way `State::tick` does.
+    pub fn run_systems(&mut self, runstate: RunState) {
+        self.ecs.insert(runstate);
+        rogue_like::run_systems(&mut self.ecs);
+        damage_system::delete_the_dead(&mut self.ecs);
+    }
+
+    /// Ticks the clock until the player's turn comes up (or they die), letting
+    /// every monster whose turn comes up before then act. Returns the ticks taken.
+    pub fn run_until_player_turn(&mut self) -> i32 {
+        let mut ticks = 0;
+        loop {
+            self.run_systems(RunState::Ticking);
+            ticks += 1;
+            if self.runstate() != RunState::Ticking {
+                return ticks;
+            }
+            assert!(ticks < 1000, "The player never got a turn");
+        }
+    }
+
+    pub fn position(&self, entity: Entity) -> (i32, i32) {
+        let positions = self.ecs.read_storage::<Position>();
+        let pos = positions.get(entity).expect("Entity has no position");
+        (pos.x, pos.y)
+    }
+
+    pub fn hp(&self, entity: Entity) -> i32 {
+        self.ecs
+            .read_storage::<CombatStats>()
+            .get(entity)
+            .expect("Entity has no combat stats")
+            .hp
+    }
+
+    pub fn log(&self) -> Vec<String> {
+        self.ecs.fetch::<GameLog>().entries.clone()
+    }
+
+    pub fn run_stats(&self) -> RunStats {
+        (*self.ecs.fetch::<RunStats>()).clone()
+    }
+
+    pub fn runstate(&self) -> RunState {
+        *self.ecs.fetch::<RunState>()
+    }
+
+    pub fn map(&self) -> Map {
+        (*self.ecs.fetch::<Map>()).clone()
+    }
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use common::TestWorld;
+use rogue_like::{player, spawner, BlocksVisibility, Door, RunState, Viewshed};
+use specs::prelude::*;
+
+const CORRIDOR: [&str; 3] = ["########", "#@+....#", "########"];
+
+fn the_door(world: &TestWorld) -> Entity {
+    let entities = world.ecs.entities();
+    let doors = world.ecs.read_storage::<Door>();
+    let (door, _door) = (&entities, &doors).join().next().expect("No door");
+    door
+}
+
+fn is_open(world: &TestWorld, door: Entity) -> bool {
+    world.ecs.read_storage::<Door>().get(door).unwrap().open
+}
+
+fn revealed(world: &TestWorld, x: i32, y: i32) -> bool {
+    let map = world.map();
+    map.revealed_tiles[map.xy_idx(x, y)]
+}
+
+#[test]
+fn closed_door_blocks_sight() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    spawner::door(&mut world.ecs, 2, 1);
+    world.run_systems(RunState::PreRun);
+
+    assert!(revealed(&world, 2, 1));
+    assert!(!revealed(&world, 3, 1));
+}
+
+#[test]
+fn walking_into_a_door_opens_it_without_moving() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    spawner::door(&mut world.ecs, 2, 1);
+    world.run_systems(RunState::PreRun);
+    let door = the_door(&world);
+
+    player::try_move_player(1, 0, &mut world.ecs);
+    world.run_systems(RunState::PlayerTurn);
+
+    assert!(is_open(&world, door));
+    assert!(world
+        .ecs
+        .read_storage::<BlocksVisibility>()
+        .get(door)
+        .is_none());
+    assert_eq!(world.position(world.player), (1, 1));
+
+    world.run_until_player_turn();
+    assert!(revealed(&world, 5, 1));
+
+    player::try_move_player(1, 0, &mut world.ecs);
+    assert_eq!(world.position(world.player), (2, 1));
+}
+
+#[test]
+fn monsters_open_doors_in_their_way() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    spawner::door(&mut world.ecs, 2, 1);
+    let goblin = world.spawn_monster("Goblin", 3, 1, 10, 0, 4);
+    world.run_systems(RunState::PreRun);
+    let door = the_door(&world);
+
+    // A barred gate: shut, but the goblin can see the player through it
+    world.ecs.write_storage::<BlocksVisibility>().remove(door);
+    for viewshed in (&mut world.ecs.write_storage::<Viewshed>()).join() {
+        viewshed.dirty = true;
+    }
+    world.run_systems(RunState::PreRun);
+
+    for _ in 0..2 {
+        world.run_until_player_turn();
+        world.run_systems(RunState::PlayerTurn);
+    }
+
+    assert!(is_open(&world, door));
+    assert_eq!(world.position(goblin), (2, 1));
+}
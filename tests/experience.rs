@@ -0,0 +1,97 @@
+mod common;
+
+use common::TestWorld;
+use rogue_like::{Experience, RunState, SufferDamage, XpValue};
+use specs::prelude::*;
+
+const CORRIDOR: [&str; 3] = ["########", "#@.....#", "########"];
+
+fn experience(world: &TestWorld, entity: specs::Entity) -> (i32, i32) {
+    let experience = world.ecs.read_storage::<Experience>();
+    let progress = experience.get(entity).unwrap();
+    (progress.level, progress.xp)
+}
+
+fn spawn_worth(world: &mut TestWorld, x: i32, hp: i32, xp: i32) -> specs::Entity {
+    let monster = world.spawn_monster("Goblin", x, 1, hp, 0, 4);
+    world
+        .ecs
+        .write_storage::<XpValue>()
+        .insert(monster, XpValue { amount: xp })
+        .unwrap();
+    monster
+}
+
+#[test]
+fn killing_blow_earns_the_monsters_xp() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let goblin = spawn_worth(&mut world, 2, 3, 25);
+    world.run_systems(RunState::PreRun);
+
+    world.attack(world.player, goblin);
+    world.run_systems(RunState::PlayerTurn);
+
+    assert_eq!(experience(&world, world.player), (1, 25));
+}
+
+#[test]
+fn wounding_without_killing_earns_nothing() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let goblin = spawn_worth(&mut world, 2, 16, 25);
+    world.run_systems(RunState::PreRun);
+
+    world.attack(world.player, goblin);
+    world.run_systems(RunState::PlayerTurn);
+
+    assert_eq!(experience(&world, world.player), (1, 0));
+}
+
+#[test]
+fn only_the_killing_blow_is_credited() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let goblin = spawn_worth(&mut world, 3, 10, 25);
+    let rival = world.spawn_monster("Rival", 5, 1, 10, 0, 4);
+    world
+        .ecs
+        .write_storage::<Experience>()
+        .insert(rival, Experience { level: 1, xp: 0 })
+        .unwrap();
+    world.run_systems(RunState::PreRun);
+
+    {
+        let mut damage = world.ecs.write_storage::<SufferDamage>();
+        SufferDamage::new_damage(&mut damage, goblin, 6, Some(rival));
+        SufferDamage::new_damage(&mut damage, goblin, 6, Some(world.player));
+        SufferDamage::new_damage(&mut damage, goblin, 6, Some(rival));
+    }
+    world.run_systems(RunState::PlayerTurn);
+
+    assert_eq!(experience(&world, world.player), (1, 25));
+    assert_eq!(experience(&world, rival), (1, 0));
+}
+
+#[test]
+fn reaching_the_threshold_levels_up() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let goblin = spawn_worth(&mut world, 2, 3, 120);
+    world.run_systems(RunState::PreRun);
+    // Take some damage first, to see it healed on the level up
+    world
+        .ecs
+        .write_storage::<rogue_like::CombatStats>()
+        .get_mut(world.player)
+        .unwrap()
+        .hp = 10;
+
+    world.attack(world.player, goblin);
+    world.run_systems(RunState::PlayerTurn);
+
+    assert_eq!(experience(&world, world.player), (2, 20));
+    let stats = world.ecs.read_storage::<rogue_like::CombatStats>();
+    let stats = stats.get(world.player).unwrap();
+    assert_eq!((stats.max_hp, stats.hp), (40, 40));
+    assert_eq!((stats.power, stats.defense), (6, 3));
+    assert!(world
+        .log()
+        .contains(&"Congratulations, you are now level 2!".to_string()));
+}
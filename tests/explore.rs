@@ -0,0 +1,139 @@
+mod common;
+
+use common::TestWorld;
+use rogue_like::autopilot::{self, Autopilot};
+use rogue_like::{player, CombatStats, HungerClock, HungerState, Item, Name, Position, RunState};
+use specs::prelude::*;
+
+const ROOM: [&str; 3] = ["#####", "#@..#", "#####"];
+
+// Longer than the player can see down, so the far end starts out unexplored
+const CORRIDOR: [&str; 3] = [
+    "################################",
+    "#@.............................#",
+    "################################",
+];
+
+/// Lets the autopilot walk until it hands control back. Returns the steps taken.
+fn explore(world: &mut TestWorld) -> i32 {
+    let mut steps = 0;
+    let mut runstate = autopilot::start_explore(&mut world.ecs);
+    while runstate == RunState::PlayerTurn {
+        steps += 1;
+        assert!(steps < 200, "The autopilot never stopped");
+        world.run_systems(RunState::PlayerTurn);
+        world.run_until_player_turn();
+        runstate = autopilot::step(&mut world.ecs).unwrap_or(RunState::AwaitingInput);
+    }
+    steps
+}
+
+fn set_hp(world: &mut TestWorld, hp: i32) {
+    let mut stats = world.ecs.write_storage::<CombatStats>();
+    stats.get_mut(world.player).unwrap().hp = hp;
+}
+
+#[test]
+fn explore_heads_for_unrevealed_tiles() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    world.run_systems(RunState::PreRun);
+
+    assert_eq!(autopilot::explore_step(&world.ecs), Some((1, 0)));
+}
+
+#[test]
+fn explore_walks_until_everything_is_revealed() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    world.run_systems(RunState::PreRun);
+
+    assert!(explore(&mut world) > 0);
+
+    let map = world.map();
+    let far_end = map.xy_idx(30, 1);
+    assert!(map.revealed_tiles[far_end]);
+    assert_eq!(world.log()[0], "There is nothing left to explore.");
+    assert!(*world.ecs.fetch::<Autopilot>() == Autopilot::Off);
+}
+
+#[test]
+fn explore_stops_when_a_monster_comes_into_view() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let goblin = world.spawn_monster("Goblin", 28, 1, 10, 0, 4);
+    world.run_systems(RunState::PreRun);
+
+    explore(&mut world);
+
+    let (player_x, _) = world.position(world.player);
+    let (goblin_x, _) = world.position(goblin);
+    assert!(goblin_x - player_x > 1);
+    assert_eq!(world.log()[0], "You spot a Goblin.");
+    assert!(!autopilot::is_active(&world.ecs));
+}
+
+#[test]
+fn explore_stops_when_a_new_item_is_seen() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    world
+        .ecs
+        .create_entity()
+        .with(Item {})
+        .with(Name {
+            name: "Rations".to_string(),
+        })
+        .with(Position { x: 20, y: 1 })
+        .build();
+    world.run_systems(RunState::PreRun);
+
+    explore(&mut world);
+
+    assert!(world.position(world.player).0 < 20);
+    assert_eq!(world.log()[0], "You see a Rations.");
+}
+
+#[test]
+fn explore_refuses_to_start_with_a_monster_in_view() {
+    let mut world = TestWorld::new(&ROOM);
+    world.spawn_monster("Goblin", 3, 1, 10, 0, 4);
+    world.run_systems(RunState::PreRun);
+
+    assert!(autopilot::start_explore(&mut world.ecs) == RunState::AwaitingInput);
+    assert_eq!(world.position(world.player), (1, 1));
+    assert!(!autopilot::is_active(&world.ecs));
+}
+
+#[test]
+fn resting_alone_heals_one_hp() {
+    let mut world = TestWorld::new(&ROOM);
+    world.run_systems(RunState::PreRun);
+    set_hp(&mut world, 10);
+
+    assert!(player::skip_turn(&mut world.ecs) == RunState::PlayerTurn);
+    assert_eq!(world.hp(world.player), 11);
+}
+
+#[test]
+fn resting_while_hungry_does_not_heal() {
+    let mut world = TestWorld::new(&ROOM);
+    world.run_systems(RunState::PreRun);
+    set_hp(&mut world, 10);
+    world
+        .ecs
+        .write_storage::<HungerClock>()
+        .get_mut(world.player)
+        .unwrap()
+        .state = HungerState::Hungry;
+
+    player::skip_turn(&mut world.ecs);
+    assert_eq!(world.hp(world.player), 10);
+}
+
+#[test]
+fn resting_with_a_monster_in_view_does_not_heal() {
+    let mut world = TestWorld::new(&ROOM);
+    world.spawn_monster("Goblin", 3, 1, 10, 0, 4);
+    world.run_systems(RunState::PreRun);
+    set_hp(&mut world, 10);
+
+    player::skip_turn(&mut world.ecs);
+    assert_eq!(world.hp(world.player), 10);
+}
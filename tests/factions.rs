@@ -0,0 +1,229 @@
+mod common;
+
+use common::TestWorld;
+use rltk::Point;
+use rogue_like::{
+    player, spawner, AiState, BlocksTile, Door, InBackpack, Position, RunState, State, TileType,
+    WantsToMelee, WantsToUseItem,
+};
+use specs::prelude::*;
+
+const CORRIDOR: [&str; 3] = ["##########", "#@.......#", "##########"];
+
+/// Two rooms, the player's and one behind a wall they can't see into.
+const TWO_ROOMS: [&str; 4] = [
+    "####################",
+    "#@.........#.......#",
+    "#..........#.......#",
+    "####################",
+];
+
+#[test]
+fn monsters_of_opposing_factions_fight_each_other() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let goblin = world.spawn_monster("Goblin", 5, 1, 16, 0, 4);
+    let zombie = world.spawn_monster("Zombie", 6, 1, 16, 0, 4);
+    world.set_faction(zombie, "Undead");
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+
+    // Each goes for the other rather than the player further away
+    assert_eq!(world.hp(goblin), 12);
+    assert_eq!(world.hp(zombie), 12);
+    assert_eq!(world.hp(world.player), 30);
+}
+
+#[test]
+fn only_the_players_kills_are_counted() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let goblin = world.spawn_monster("Goblin", 5, 1, 4, 0, 4);
+    let zombie = world.spawn_monster("Zombie", 6, 1, 16, 0, 10);
+    world.set_faction(zombie, "Undead");
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+
+    assert!(!world.ecs.is_alive(goblin));
+    assert_eq!(world.run_stats().kills, 0);
+}
+
+#[test]
+fn neutral_creature_ignores_the_player_until_provoked() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let bat = world.spawn_monster("Bat", 2, 1, 20, 0, 4);
+    world.set_faction(bat, "Wildlife");
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+    assert_eq!(world.hp(world.player), 30);
+    assert!(player::monster_in_view(&world.ecs).is_none());
+
+    world.attack(world.player, bat);
+    world.run_systems(RunState::PlayerTurn);
+    world.run_until_player_turn();
+
+    // Player defense is 2
+    assert_eq!(world.hp(world.player), 28);
+    assert_eq!(player::monster_in_view(&world.ecs), Some(bat));
+}
+
+#[test]
+fn creature_runs_from_what_it_fears() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let zombie = world.spawn_monster("Zombie", 2, 1, 16, 0, 4);
+    world.set_faction(zombie, "Undead");
+    let bat = world.spawn_monster("Bat", 4, 1, 6, 0, 3);
+    world.set_faction(bat, "Wildlife");
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+
+    assert_eq!(world.position(bat), (5, 1));
+    assert_eq!(world.ai_state(bat), AiState::Flee);
+}
+
+#[test]
+fn ally_follows_the_player() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let dog = world.spawn_monster("Dog", 6, 1, 12, 0, 3);
+    world.set_faction(dog, "Player");
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+    assert_eq!(world.position(dog), (5, 1));
+    assert!(player::monster_in_view(&world.ecs).is_none());
+
+    // Close enough already
+    for _ in 0..5 {
+        world.run_until_player_turn();
+    }
+    assert_eq!(world.position(dog), (3, 1));
+    assert_eq!(world.hp(world.player), 30);
+}
+
+#[test]
+fn ally_fights_the_players_enemies() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let dog = world.spawn_monster("Dog", 3, 1, 12, 0, 3);
+    world.set_faction(dog, "Player");
+    let goblin = world.spawn_monster("Goblin", 4, 1, 16, 0, 4);
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+
+    assert_eq!(world.hp(goblin), 13);
+    assert_eq!(world.hp(dog), 8);
+}
+
+#[test]
+fn player_swaps_places_with_an_ally() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let dog = world.spawn_monster("Dog", 2, 1, 12, 0, 3);
+    world.set_faction(dog, "Player");
+    world.run_systems(RunState::PreRun);
+
+    player::try_move_player(1, 0, &mut world.ecs);
+
+    assert_eq!(world.position(world.player), (2, 1));
+    assert_eq!(world.position(dog), (1, 1));
+    assert_eq!(world.hp(dog), 12);
+}
+
+#[test]
+fn ally_caught_in_the_players_blast_can_be_fought_back() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let dog = world.spawn_monster("Dog", 2, 1, 40, 0, 3);
+    world.set_faction(dog, "Player");
+    world.run_systems(RunState::PreRun);
+
+    let scroll = spawner::spawn_named_item(&mut world.ecs, "Fireball Scroll", 0, 0).unwrap();
+    world.ecs.write_storage::<Position>().remove(scroll);
+    world
+        .ecs
+        .write_storage::<InBackpack>()
+        .insert(
+            scroll,
+            InBackpack {
+                owner: world.player,
+            },
+        )
+        .unwrap();
+    world
+        .ecs
+        .write_storage::<WantsToUseItem>()
+        .insert(
+            world.player,
+            WantsToUseItem {
+                item: scroll,
+                target: Some(Point::new(5, 1)),
+            },
+        )
+        .unwrap();
+    world.run_systems(RunState::PlayerTurn);
+    // The damage lands on the next pass
+    world.run_systems(RunState::PlayerTurn);
+    assert_eq!(world.hp(dog), 20);
+
+    // The dog has turned on the player, so walking into it is an attack, not a swap
+    player::try_move_player(1, 0, &mut world.ecs);
+
+    assert_eq!(world.position(world.player), (1, 1));
+    assert_eq!(
+        world
+            .ecs
+            .read_storage::<WantsToMelee>()
+            .get(world.player)
+            .unwrap()
+            .target,
+        dog
+    );
+}
+
+#[test]
+fn nearby_allies_follow_the_player_down_to_tiles_of_their_own() {
+    let mut world = TestWorld::new(&TWO_ROOMS);
+    let mut followers = Vec::new();
+    for (x, y) in [(2, 1), (1, 2), (2, 2), (8, 1)].iter() {
+        let dog = world.spawn_monster("Dog", *x, *y, 12, 0, 3);
+        world.set_faction(dog, "Player");
+        followers.push(dog);
+    }
+    let next_room = world.spawn_monster("Dog", 15, 1, 12, 0, 3);
+    world.set_faction(next_room, "Player");
+    world.run_systems(RunState::PreRun);
+    let player = world.player;
+
+    let mut state = State::new(false);
+    state.ecs = world.ecs;
+    state.goto_next_level();
+
+    // Out of sight and too far away to notice the player leave
+    assert!(!state.ecs.is_alive(next_room));
+
+    let map = state.ecs.fetch::<rogue_like::Map>();
+    let entities = state.ecs.entities();
+    let positions = state.ecs.read_storage::<Position>();
+    let blockers = state.ecs.read_storage::<BlocksTile>();
+    let doors = state.ecs.read_storage::<Door>();
+    let player_pos = positions.get(player).unwrap();
+    let mut taken = vec![(player_pos.x, player_pos.y)];
+    for (entity, pos, _blocks) in (&entities, &positions, &blockers).join() {
+        if !followers.contains(&entity) {
+            taken.push((pos.x, pos.y));
+        }
+    }
+    // Nobody gets put inside a closed door either
+    for (pos, door) in (&positions, &doors).join() {
+        if !door.open {
+            taken.push((pos.x, pos.y));
+        }
+    }
+    for dog in followers {
+        assert!(state.ecs.is_alive(dog));
+        let pos = positions.get(dog).unwrap();
+        assert_eq!(map.tiles[map.xy_idx(pos.x, pos.y)], TileType::Floor);
+        assert!(!taken.contains(&(pos.x, pos.y)), "two on one tile");
+        taken.push((pos.x, pos.y));
+    }
+}
@@ -0,0 +1,28 @@
+use rogue_like::headless::{self, RandomPolicy, ScriptedPolicy};
+
+#[test]
+fn same_seed_plays_out_the_same() {
+    let first = headless::simulate(42, 200, &mut RandomPolicy::new(42));
+    let second = headless::simulate(42, 200, &mut RandomPolicy::new(42));
+
+    assert_eq!(first.died, second.died);
+    assert_eq!(first.turns, second.turns);
+    assert_eq!(first.damage_dealt, second.damage_dealt);
+    assert_eq!(first.kills, second.kills);
+    assert_eq!(first.depth, second.depth);
+}
+
+#[test]
+fn run_stops_after_the_turn_limit() {
+    let mut policy = ScriptedPolicy::from_keys("hjkl").unwrap();
+    let report = headless::simulate(7, 25, &mut policy);
+
+    assert!(report.turns <= 25);
+    assert!(report.died || report.turns == 25);
+}
+
+#[test]
+fn scripts_reject_unknown_keys() {
+    assert!(ScriptedPolicy::from_keys("hjkz").is_err());
+    assert!(ScriptedPolicy::from_keys("").is_err());
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::TestWorld;
+use rogue_like::hunger_system::HUNGER_STATE_DURATION;
+use rogue_like::{spawner, HungerClock, HungerState, RunState, WantsToUseItem};
+use specs::prelude::*;
+
+const ROOM: [&str; 3] = ["#####", "#@..#", "#####"];
+
+fn set_hunger(world: &mut TestWorld, state: HungerState, duration: i32) {
+    let mut clocks = world.ecs.write_storage::<HungerClock>();
+    let clock = clocks.get_mut(world.player).unwrap();
+    clock.state = state;
+    clock.duration = duration;
+}
+
+fn hunger(world: &TestWorld) -> (HungerState, i32) {
+    let clocks = world.ecs.read_storage::<HungerClock>();
+    let clock = clocks.get(world.player).unwrap();
+    (clock.state, clock.duration)
+}
+
+#[test]
+fn clock_counts_down_once_per_player_turn() {
+    let mut world = TestWorld::new(&ROOM);
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+    world.run_systems(RunState::PlayerTurn);
+    world.run_until_player_turn();
+
+    assert_eq!(
+        hunger(&world),
+        (HungerState::WellFed, HUNGER_STATE_DURATION - 2)
+    );
+}
+
+#[test]
+fn running_out_of_time_moves_to_the_next_state() {
+    let mut world = TestWorld::new(&ROOM);
+    set_hunger(&mut world, HungerState::Normal, 1);
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+
+    assert_eq!(hunger(&world), (HungerState::Hungry, HUNGER_STATE_DURATION));
+    assert_eq!(world.log()[0], "You are hungry.");
+}
+
+#[test]
+fn starving_hurts_every_turn() {
+    let mut world = TestWorld::new(&ROOM);
+    set_hunger(&mut world, HungerState::Starving, 0);
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+    world.run_until_player_turn();
+
+    assert_eq!(world.hp(world.player), 28);
+    assert_eq!(
+        world.run_stats().last_hit_by,
+        Some("starvation".to_string())
+    );
+}
+
+#[test]
+fn eating_leaves_the_player_well_fed() {
+    let mut world = TestWorld::new(&ROOM);
+    set_hunger(&mut world, HungerState::Hungry, 5);
+    let rations = spawner::spawn_named_item(&mut world.ecs, "Rations", 1, 1).unwrap();
+    world.run_systems(RunState::PreRun);
+    world
+        .ecs
+        .write_storage::<WantsToUseItem>()
+        .insert(
+            world.player,
+            WantsToUseItem {
+                item: rations,
+                target: None,
+            },
+        )
+        .expect("Unable to insert use");
+
+    world.run_systems(RunState::PlayerTurn);
+
+    assert_eq!(
+        hunger(&world),
+        (HungerState::WellFed, HUNGER_STATE_DURATION)
+    );
+    assert_eq!(world.log()[0], "You eat the Rations.");
+    assert!(!world.ecs.is_alive(rations));
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use common::TestWorld;
+use rogue_like::initiative_system::{ACTION_COST, NORMAL_SPEED};
+use rogue_like::{MyTurn, RunState};
+use specs::prelude::*;
+
+const CORRIDOR: [&str; 3] = ["##########", "#@.......#", "##########"];
+
+#[test]
+fn player_turn_comes_up_once_they_have_the_energy() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    world.run_systems(RunState::PreRun);
+
+    let ticks = world.run_until_player_turn();
+
+    assert_eq!(ticks, ACTION_COST / NORMAL_SPEED);
+    assert!(world.runstate() == RunState::AwaitingInput);
+    assert!(world
+        .ecs
+        .read_storage::<MyTurn>()
+        .get(world.player)
+        .is_some());
+}
+
+#[test]
+fn fast_monster_acts_twice_per_player_turn() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let bat = world.spawn_monster("Bat", 7, 1, 6, 0, 3);
+    world.set_speed(bat, NORMAL_SPEED * 2);
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+
+    assert_eq!(world.position(bat), (5, 1));
+}
+
+#[test]
+fn slow_monster_acts_every_other_player_turn() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let zombie = world.spawn_monster("Zombie", 7, 1, 24, 1, 6);
+    world.set_speed(zombie, NORMAL_SPEED / 2);
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+    assert_eq!(world.position(zombie), (7, 1));
+
+    world.run_systems(RunState::PlayerTurn);
+    world.run_until_player_turn();
+    assert_eq!(world.position(zombie), (6, 1));
+}
@@ -0,0 +1,208 @@
+mod common;
+
+use common::TestWorld;
+use rltk::Point;
+use rogue_like::{
+    player, spawner, CombatStats, EquipmentSlot, Equipped, InBackpack, Name, Position, RunState,
+    WantsToDropItem, WantsToUseItem,
+};
+use specs::prelude::*;
+
+const CORRIDOR: [&str; 3] = ["########", "#@.....#", "########"];
+
+/// Puts a new `name` straight into the player's backpack.
+fn give(world: &mut TestWorld, name: &str) -> Entity {
+    let item = spawner::spawn_named_item(&mut world.ecs, name, 0, 0).expect("No such item");
+    world.ecs.write_storage::<Position>().remove(item);
+    world
+        .ecs
+        .write_storage::<InBackpack>()
+        .insert(
+            item,
+            InBackpack {
+                owner: world.player,
+            },
+        )
+        .expect("Unable to insert item in backpack");
+    item
+}
+
+/// Has the player use `item`, as picking it from the inventory would.
+fn use_item(world: &mut TestWorld, item: Entity, target: Option<Point>) {
+    world
+        .ecs
+        .write_storage::<WantsToUseItem>()
+        .insert(world.player, WantsToUseItem { item, target })
+        .expect("Unable to insert use");
+    world.run_systems(RunState::PlayerTurn);
+}
+
+fn owner_of(world: &TestWorld, item: Entity) -> Option<Entity> {
+    world
+        .ecs
+        .read_storage::<InBackpack>()
+        .get(item)
+        .map(|bp| bp.owner)
+}
+
+#[test]
+fn picking_up_an_item_moves_it_into_the_backpack() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let potion = spawner::spawn_named_item(&mut world.ecs, "Health Potion", 1, 1).unwrap();
+    world.run_systems(RunState::PreRun);
+
+    assert!(player::get_item(&mut world.ecs) == RunState::PlayerTurn);
+    world.run_systems(RunState::PlayerTurn);
+
+    assert_eq!(owner_of(&world, potion), Some(world.player));
+    assert!(world.ecs.read_storage::<Position>().get(potion).is_none());
+    assert_eq!(world.log()[0], "You pick up the Health Potion.");
+}
+
+#[test]
+fn health_potion_heals_and_is_used_up() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    world.run_systems(RunState::PreRun);
+    let potion = give(&mut world, "Health Potion");
+    world
+        .ecs
+        .write_storage::<CombatStats>()
+        .get_mut(world.player)
+        .unwrap()
+        .hp = 10;
+
+    use_item(&mut world, potion, None);
+
+    assert_eq!(world.hp(world.player), 18);
+    assert!(!world.ecs.is_alive(potion));
+}
+
+#[test]
+fn healing_past_max_hp_logs_only_what_was_healed() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    world.run_systems(RunState::PreRun);
+    let potion = give(&mut world, "Health Potion");
+    world
+        .ecs
+        .write_storage::<CombatStats>()
+        .get_mut(world.player)
+        .unwrap()
+        .hp = 27;
+
+    use_item(&mut world, potion, None);
+
+    assert_eq!(world.hp(world.player), 30);
+    assert_eq!(world.log()[0], "You use the Health Potion, healing 3 hp.");
+}
+
+#[test]
+fn magic_missile_hurts_the_target() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let goblin = world.spawn_monster("Goblin", 4, 1, 10, 0, 4);
+    world.run_systems(RunState::PreRun);
+    let scroll = give(&mut world, "Magic Missile Scroll");
+
+    use_item(&mut world, scroll, Some(Point::new(4, 1)));
+    // The damage lands on the next pass
+    world.run_systems(RunState::PlayerTurn);
+
+    assert_eq!(world.hp(goblin), 2);
+    assert!(!world.ecs.is_alive(scroll));
+}
+
+#[test]
+fn equipping_swaps_out_whatever_was_in_the_slot() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    world.run_systems(RunState::PreRun);
+    let first = give(&mut world, "Dagger");
+    let second = give(&mut world, "Dagger");
+
+    use_item(&mut world, first, None);
+    use_item(&mut world, second, None);
+
+    let equipped = world.ecs.read_storage::<Equipped>();
+    assert!(equipped.get(first).is_none());
+    assert_eq!(owner_of(&world, first), Some(world.player));
+    let slot = equipped.get(second).expect("Not equipped");
+    assert_eq!(slot.owner, world.player);
+    assert!(slot.slot == EquipmentSlot::Melee);
+}
+
+#[test]
+fn equipped_weapon_adds_to_melee_damage() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    // Player power is 5, the dagger adds 2
+    let goblin = world.spawn_monster("Goblin", 2, 1, 16, 1, 4);
+    world.run_systems(RunState::PreRun);
+    let dagger = give(&mut world, "Dagger");
+    use_item(&mut world, dagger, None);
+
+    world.attack(world.player, goblin);
+    world.run_systems(RunState::PlayerTurn);
+
+    assert_eq!(world.hp(goblin), 10);
+}
+
+#[test]
+fn dropped_item_lands_at_the_players_feet() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    world.run_systems(RunState::PreRun);
+    let potion = give(&mut world, "Health Potion");
+
+    world
+        .ecs
+        .write_storage::<WantsToDropItem>()
+        .insert(world.player, WantsToDropItem { item: potion })
+        .expect("Unable to insert drop");
+    world.run_systems(RunState::PlayerTurn);
+
+    assert_eq!(owner_of(&world, potion), None);
+    assert_eq!(world.position(potion), (1, 1));
+}
+
+#[test]
+fn items_without_a_name_still_get_logged() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    world.run_systems(RunState::PreRun);
+    let potion = give(&mut world, "Health Potion");
+    world.ecs.write_storage::<Name>().remove(potion);
+
+    world
+        .ecs
+        .write_storage::<WantsToDropItem>()
+        .insert(world.player, WantsToDropItem { item: potion })
+        .expect("Unable to insert drop");
+    world.run_systems(RunState::PlayerTurn);
+
+    assert_eq!(world.log()[0], "You drop the something.");
+}
+
+#[test]
+fn unnamed_items_are_still_swapped_out() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    world.run_systems(RunState::PreRun);
+    let first = give(&mut world, "Dagger");
+    let second = give(&mut world, "Dagger");
+    use_item(&mut world, first, None);
+    world.ecs.write_storage::<Name>().remove(first);
+
+    use_item(&mut world, second, None);
+
+    assert!(world.ecs.read_storage::<Equipped>().get(first).is_none());
+    assert_eq!(owner_of(&world, first), Some(world.player));
+    assert!(world
+        .log()
+        .contains(&"You unequip the something.".to_string()));
+}
+
+#[test]
+fn equipment_aimed_at_nobody_is_not_equipped() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    world.run_systems(RunState::PreRun);
+    let dagger = give(&mut world, "Dagger");
+
+    use_item(&mut world, dagger, Some(Point::new(4, 1)));
+
+    assert!(world.ecs.read_storage::<Equipped>().get(dagger).is_none());
+    assert_eq!(owner_of(&world, dagger), Some(world.player));
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::map_from_rows;
+use rogue_like::TileType;
+
+#[test]
+fn unreachable_floor_is_walled_over() {
+    let mut map = map_from_rows(&["##########", "#....#...#", "#....#...#", "##########"]);
+
+    map.cull_unreachable_and_place_exit(1, 1);
+
+    assert!(map.tiles[map.xy_idx(7, 1)] == TileType::Wall);
+    assert!(map.tiles[map.xy_idx(2, 2)] != TileType::Wall);
+}
+
+#[test]
+fn exit_goes_on_the_furthest_walk() {
+    // Round the bend is further to walk than the straight end of the corridor
+    let mut map = map_from_rows(&[
+        "#########",
+        "#.......#",
+        "#######.#",
+        "#.......#",
+        "#########",
+    ]);
+
+    map.cull_unreachable_and_place_exit(7, 1);
+
+    let stairs: Vec<usize> = (0..map.tiles.len())
+        .filter(|idx| map.tiles[*idx] == TileType::DownStairs)
+        .collect();
+    assert_eq!(stairs, vec![map.xy_idx(1, 3)]);
+}
+
+#[test]
+fn builder_stairs_are_replaced() {
+    let mut map = map_from_rows(&["######", "#>...#", "######"]);
+
+    map.cull_unreachable_and_place_exit(1, 1);
+
+    assert!(map.tiles[map.xy_idx(1, 1)] == TileType::Floor);
+    assert!(map.tiles[map.xy_idx(4, 1)] == TileType::DownStairs);
+}
@@ -0,0 +1,197 @@
+mod common;
+
+use common::TestWorld;
+use rogue_like::{AiBehaviour, AiState, CombatStats, Confusion, RunState};
+use specs::prelude::*;
+
+const CORRIDOR: [&str; 3] = ["##########", "#@.......#", "##########"];
+
+// The player can't see into the lower corridor from the upper one
+const TWO_CORRIDORS: [&str; 5] = [
+    "##########",
+    "#@.......#",
+    "########.#",
+    "#........#",
+    "##########",
+];
+
+fn behaviour(wanders: bool, flee_below: i32, search_turns: i32) -> AiBehaviour {
+    AiBehaviour {
+        wanders,
+        flee_below,
+        search_turns,
+    }
+}
+
+#[test]
+fn monster_steps_toward_a_player_it_can_see() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let goblin = world.spawn_monster("Goblin", 6, 1, 16, 1, 4);
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+
+    assert_eq!(world.position(goblin), (5, 1));
+}
+
+#[test]
+fn monsters_only_act_on_their_turn() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let goblin = world.spawn_monster("Goblin", 6, 1, 16, 1, 4);
+    world.run_systems(RunState::PreRun);
+
+    world.run_systems(RunState::PlayerTurn);
+
+    assert_eq!(world.position(goblin), (6, 1));
+}
+
+#[test]
+fn monster_cannot_see_through_walls() {
+    let mut world = TestWorld::new(&TWO_CORRIDORS);
+    let goblin = world.spawn_monster("Goblin", 2, 3, 16, 1, 4);
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+
+    assert_eq!(world.position(goblin), (2, 3));
+}
+
+#[test]
+fn adjacent_monster_attacks_instead_of_moving() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    // Player defense is 2
+    let goblin = world.spawn_monster("Goblin", 2, 1, 16, 1, 4);
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+
+    assert_eq!(world.position(goblin), (2, 1));
+    assert_eq!(world.hp(world.player), 28);
+    assert_eq!(world.log()[0], "Goblin hits Player, for 2 hp");
+}
+
+#[test]
+fn confused_monster_loses_its_turns() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let goblin = world.spawn_monster("Goblin", 6, 1, 16, 1, 4);
+    world
+        .ecs
+        .write_storage::<Confusion>()
+        .insert(goblin, Confusion { turns: 2 })
+        .unwrap();
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+    world.run_until_player_turn();
+    assert_eq!(world.position(goblin), (6, 1));
+
+    world.run_until_player_turn();
+    assert_eq!(world.position(goblin), (5, 1));
+}
+
+#[test]
+fn monster_searches_where_the_player_was_last_seen() {
+    let mut world = TestWorld::new(&TWO_CORRIDORS);
+    let goblin = world.spawn_monster("Goblin", 2, 3, 16, 1, 4);
+    world.set_behaviour(goblin, behaviour(false, 0, 10));
+    world.set_ai_state(goblin, AiState::Chase { x: 8, y: 3 });
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+
+    assert_eq!(world.position(goblin), (3, 3));
+    assert_eq!(
+        world.ai_state(goblin),
+        AiState::Search {
+            x: 8,
+            y: 3,
+            turns: 9
+        }
+    );
+}
+
+#[test]
+fn monster_gives_up_once_the_search_is_over() {
+    let mut world = TestWorld::new(&TWO_CORRIDORS);
+    let goblin = world.spawn_monster("Goblin", 2, 3, 16, 1, 4);
+    world.set_ai_state(
+        goblin,
+        AiState::Search {
+            x: 4,
+            y: 3,
+            turns: 10,
+        },
+    );
+    world.run_systems(RunState::PreRun);
+
+    for _ in 0..3 {
+        world.run_until_player_turn();
+    }
+
+    assert_eq!(world.position(goblin), (4, 3));
+    assert_eq!(world.ai_state(goblin), AiState::Idle);
+}
+
+#[test]
+fn monster_without_search_turns_loses_interest_at_once() {
+    let mut world = TestWorld::new(&TWO_CORRIDORS);
+    let goblin = world.spawn_monster("Goblin", 2, 3, 16, 1, 4);
+    world.set_ai_state(goblin, AiState::Chase { x: 8, y: 3 });
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+
+    assert_eq!(world.position(goblin), (2, 3));
+    assert_eq!(world.ai_state(goblin), AiState::Idle);
+}
+
+#[test]
+fn badly_hurt_monster_runs_away() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let goblin = world.spawn_monster("Goblin", 3, 1, 16, 1, 4);
+    world.set_behaviour(goblin, behaviour(false, 50, 0));
+    world
+        .ecs
+        .write_storage::<CombatStats>()
+        .get_mut(goblin)
+        .unwrap()
+        .hp = 5;
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+
+    assert_eq!(world.position(goblin), (4, 1));
+    assert_eq!(world.ai_state(goblin), AiState::Flee);
+}
+
+#[test]
+fn cornered_monster_fights_back() {
+    let mut world = TestWorld::new(&["####", "#@.#", "####"]);
+    let goblin = world.spawn_monster("Goblin", 2, 1, 16, 1, 4);
+    world.set_behaviour(goblin, behaviour(false, 50, 0));
+    world
+        .ecs
+        .write_storage::<CombatStats>()
+        .get_mut(goblin)
+        .unwrap()
+        .hp = 5;
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+
+    assert_eq!(world.position(goblin), (2, 1));
+    assert_eq!(world.hp(world.player), 28);
+}
+
+#[test]
+fn idle_monster_wanders_if_it_likes() {
+    let mut world = TestWorld::new(&TWO_CORRIDORS);
+    let goblin = world.spawn_monster("Goblin", 2, 3, 16, 1, 4);
+    world.set_behaviour(goblin, behaviour(true, 0, 0));
+    world.run_systems(RunState::PreRun);
+
+    world.run_until_player_turn();
+
+    assert_ne!(world.position(goblin), (2, 3));
+    assert_eq!(world.ai_state(goblin), AiState::Idle);
+}
@@ -0,0 +1,49 @@
+use rogue_like::raws;
+
+const SPAWNS: &str = include_str!("../raws/spawns.json");
+
+#[test]
+fn shipped_raws_are_valid() {
+    assert!(raws::parse_raws("spawns.json", SPAWNS).is_ok());
+}
+
+#[test]
+fn bad_color_is_rejected() {
+    let data = SPAWNS.replacen("\"fg\": \"#FF0000\"", "\"fg\": \"red\"", 1);
+
+    let error = raws::parse_raws("spawns.json", &data).err().unwrap();
+    assert_eq!(error, "Goblin in spawns.json has invalid color red");
+}
+
+#[test]
+fn unknown_builder_is_rejected() {
+    let data = SPAWNS.replacen("\"builder\": \"simple\"", "\"builder\": \"simpel\"", 1);
+
+    let error = raws::parse_raws("spawns.json", &data).err().unwrap();
+    assert_eq!(
+        error,
+        "Level 1 in spawns.json asks for unknown builder simpel"
+    );
+}
+
+#[test]
+fn missing_player_faction_is_rejected() {
+    let data = SPAWNS.replace("\"Player\"", "\"Hero\"");
+
+    let error = raws::parse_raws("spawns.json", &data).err().unwrap();
+    assert_eq!(
+        error,
+        "Player in spawns.json belongs to unknown faction Player"
+    );
+}
+
+#[test]
+fn response_to_unknown_faction_is_rejected() {
+    let data = SPAWNS.replacen("\"Undead\": \"flee\"", "\"Undaed\": \"flee\"", 1);
+
+    let error = raws::parse_raws("spawns.json", &data).err().unwrap();
+    assert_eq!(
+        error,
+        "Wildlife in spawns.json reacts to unknown faction Undaed"
+    );
+}
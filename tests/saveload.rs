@@ -0,0 +1,199 @@
+mod common;
+
+use common::TestWorld;
+use rltk::RandomNumberGenerator;
+use rogue_like::saveload_system;
+use rogue_like::{
+    spawner, EquipmentSlot, Equipped, InBackpack, Name, Position, Provoked, State, WantsToMelee,
+};
+use specs::prelude::*;
+
+const ROOM: [&str; 3] = ["#####", "#@..#", "#####"];
+
+/// A save file of its own for each test, so they can run side by side.
+fn save_path(test: &str) -> String {
+    std::env::temp_dir()
+        .join(format!("rogue_like_{}_{}.json", test, std::process::id()))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// The entity called `name`.
+fn named(world: &TestWorld, name: &str) -> Entity {
+    let entities = world.ecs.entities();
+    let names = world.ecs.read_storage::<Name>();
+    (&entities, &names)
+        .join()
+        .find(|(_, n)| n.name == name)
+        .map(|(entity, _)| entity)
+        .expect("Nothing by that name")
+}
+
+/// The player after a load, which is a new entity.
+fn loaded_player(world: &TestWorld) -> Entity {
+    *world.ecs.fetch::<Entity>()
+}
+
+/// Saves a game where a provoked goblin is about to hit the player, who carries
+/// a potion and wields a dagger.
+fn save_fight(path: &str) {
+    let mut world = TestWorld::new(&ROOM);
+    let player = world.player;
+
+    let goblin = spawner::spawn_named_monster(&mut world.ecs, "Goblin", 2, 1).unwrap();
+    world
+        .ecs
+        .write_storage::<Provoked>()
+        .insert(goblin, Provoked { by: player })
+        .unwrap();
+    world
+        .ecs
+        .write_storage::<WantsToMelee>()
+        .insert(goblin, WantsToMelee { target: player })
+        .unwrap();
+
+    let potion = spawner::spawn_named_item(&mut world.ecs, "Health Potion", 0, 0).unwrap();
+    world.ecs.write_storage::<Position>().remove(potion);
+    world
+        .ecs
+        .write_storage::<InBackpack>()
+        .insert(potion, InBackpack { owner: player })
+        .unwrap();
+
+    let dagger = spawner::spawn_named_item(&mut world.ecs, "Dagger", 0, 0).unwrap();
+    world.ecs.write_storage::<Position>().remove(dagger);
+    world
+        .ecs
+        .write_storage::<Equipped>()
+        .insert(
+            dagger,
+            Equipped {
+                owner: player,
+                slot: EquipmentSlot::Melee,
+            },
+        )
+        .unwrap();
+
+    saveload_system::save_game(&mut world.ecs, path).expect("Unable to save");
+}
+
+#[test]
+fn loading_points_references_at_the_loaded_entities() {
+    let path = save_path("references");
+    save_fight(&path);
+
+    let mut world = TestWorld::new(&ROOM);
+    saveload_system::load_game(&mut world.ecs, &path).expect("Unable to load");
+    saveload_system::delete_save(&path).expect("Unable to delete save");
+
+    let player = loaded_player(&world);
+    assert!(world.ecs.is_alive(player));
+    assert_ne!(
+        player, world.player,
+        "the old player should have been replaced"
+    );
+
+    let goblin = named(&world, "Goblin");
+    assert_eq!(
+        world.ecs.read_storage::<Provoked>().get(goblin).unwrap().by,
+        player
+    );
+    assert_eq!(
+        world
+            .ecs
+            .read_storage::<WantsToMelee>()
+            .get(goblin)
+            .unwrap()
+            .target,
+        player
+    );
+
+    let potion = named(&world, "Health Potion");
+    assert_eq!(
+        world
+            .ecs
+            .read_storage::<InBackpack>()
+            .get(potion)
+            .unwrap()
+            .owner,
+        player
+    );
+
+    let dagger = named(&world, "Dagger");
+    let equipped = world.ecs.read_storage::<Equipped>();
+    let equipped = equipped.get(dagger).unwrap();
+    assert_eq!(equipped.owner, player);
+    assert_eq!(equipped.slot, EquipmentSlot::Melee);
+}
+
+#[test]
+fn a_loaded_game_rolls_the_same_whatever_came_before() {
+    let path = save_path("rng");
+    save_fight(&path);
+
+    let mut first = TestWorld::new(&ROOM);
+    let mut second = TestWorld::new(&ROOM);
+    second.ecs.insert(RandomNumberGenerator::seeded(2));
+    saveload_system::load_game(&mut first.ecs, &path).expect("Unable to load");
+    saveload_system::load_game(&mut second.ecs, &path).expect("Unable to load");
+    saveload_system::delete_save(&path).expect("Unable to delete save");
+
+    let rolls = |world: &TestWorld| {
+        let mut rng = world.ecs.write_resource::<RandomNumberGenerator>();
+        (0..10).map(|_| rng.roll_dice(1, 100)).collect::<Vec<_>>()
+    };
+    assert_eq!(rolls(&first), rolls(&second));
+}
+
+#[test]
+fn a_failed_save_leaves_the_world_as_it_was() {
+    let mut world = TestWorld::new(&ROOM);
+    let before = world.ecs.entities().join().count();
+
+    let path = std::env::temp_dir()
+        .join("rogue_like_no_such_dir")
+        .join("save.json");
+    let result = saveload_system::save_game(&mut world.ecs, &path.to_string_lossy());
+
+    assert!(result.is_err());
+    world.ecs.maintain();
+    assert_eq!(world.ecs.entities().join().count(), before);
+}
+
+#[test]
+fn continuing_a_saved_game_uses_the_save_up() {
+    let path = save_path("continue");
+    save_fight(&path);
+
+    let mut state = State::new(false);
+    // Loading replaces the game the menu was started over, as in main
+    state.new_game(1);
+    state.load_game(&path).expect("Unable to load");
+
+    assert!(!saveload_system::does_save_exist(&path));
+}
+
+#[test]
+fn a_save_that_fails_to_load_is_kept() {
+    let path = save_path("corrupt");
+    std::fs::write(&path, "not a save").unwrap();
+
+    let mut state = State::new(false);
+    state.new_game(1);
+    assert!(state.load_game(&path).is_err());
+
+    assert!(saveload_system::does_save_exist(&path));
+    saveload_system::delete_save(&path).expect("Unable to delete save");
+}
+
+#[test]
+fn a_save_that_cannot_be_deleted_is_reported() {
+    // A directory can't be removed as a file
+    let path = save_path("undeletable");
+    std::fs::create_dir_all(&path).unwrap();
+
+    let result = saveload_system::delete_save(&path);
+
+    assert!(result.is_err());
+    std::fs::remove_dir(&path).unwrap();
+}
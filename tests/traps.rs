@@ -0,0 +1,149 @@
+mod common;
+
+use common::TestWorld;
+use rltk::{Point, RandomNumberGenerator};
+use rogue_like::{player, spawner, AiState, Hidden, RunState};
+use specs::prelude::*;
+
+const CORRIDOR: [&str; 3] = [
+    "################################",
+    "#@.............................#",
+    "################################",
+];
+
+fn spawn_trap(world: &mut TestWorld, name: &str, x: i32, y: i32) -> Entity {
+    spawner::spawn_named_trap(&mut world.ecs, name, x, y).expect("No such trap in the raws")
+}
+
+fn is_hidden(world: &TestWorld, entity: Entity) -> bool {
+    world.ecs.read_storage::<Hidden>().get(entity).is_some()
+}
+
+/// Steps the player one tile right and plays out the turn.
+fn step_right(world: &mut TestWorld) {
+    player::try_move_player(1, 0, &mut world.ecs);
+    world.run_systems(RunState::PlayerTurn);
+}
+
+#[test]
+fn traps_start_hidden() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let trap = spawn_trap(&mut world, "Bear Trap", 2, 1);
+
+    assert!(is_hidden(&world, trap));
+}
+
+#[test]
+fn bear_trap_hurts_and_disarms_after_firing() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let trap = spawn_trap(&mut world, "Bear Trap", 2, 1);
+    world.run_systems(RunState::PreRun);
+    let hp = world.hp(world.player);
+
+    step_right(&mut world);
+
+    assert_eq!(world.hp(world.player), hp - 6);
+    assert!(world
+        .log()
+        .contains(&"You set off a Bear Trap!".to_string()));
+    assert!(!world.ecs.is_alive(trap));
+    assert_eq!(
+        world.run_stats().last_hit_by,
+        Some("a Bear Trap".to_string())
+    );
+}
+
+#[test]
+fn teleport_trap_moves_the_player_and_stays_armed() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let trap = spawn_trap(&mut world, "Teleport Trap", 2, 1);
+    world.run_systems(RunState::PreRun);
+
+    step_right(&mut world);
+
+    let (x, y) = world.position(world.player);
+    assert_ne!((x, y), (2, 1));
+    assert_eq!(*world.ecs.fetch::<Point>(), Point::new(x, y));
+    assert!(world.ecs.is_alive(trap));
+    assert!(!is_hidden(&world, trap));
+}
+
+#[test]
+fn teleport_trap_never_lands_anyone_in_a_closed_door() {
+    // Apart from the trap, the only floor is the door and the tile the player left
+    for seed in 1..=10 {
+        let mut world = TestWorld::new(&["#####", "#@..#", "#####"]);
+        world.ecs.insert(RandomNumberGenerator::seeded(seed));
+        spawn_trap(&mut world, "Teleport Trap", 2, 1);
+        spawner::door(&mut world.ecs, 3, 1);
+        world.run_systems(RunState::PreRun);
+
+        step_right(&mut world);
+
+        assert_eq!(world.position(world.player), (1, 1));
+    }
+}
+
+#[test]
+fn alarm_sends_monsters_in_earshot_to_investigate() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    spawn_trap(&mut world, "Alarm Trap", 2, 1);
+    let goblin = world.spawn_monster("Goblin", 14, 1, 10, 0, 4);
+    world.run_systems(RunState::PreRun);
+
+    step_right(&mut world);
+
+    match world.ai_state(goblin) {
+        AiState::Search { x, y, .. } => assert_eq!((x, y), (2, 1)),
+        state => panic!("The goblin didn't hear it: {:?}", state),
+    }
+    world.run_until_player_turn();
+    assert!(world.position(goblin).0 < 14);
+}
+
+#[test]
+fn alarm_does_not_send_allies_to_investigate() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    spawn_trap(&mut world, "Alarm Trap", 2, 1);
+    let dog = world.spawn_monster("Dog", 14, 1, 12, 0, 3);
+    world.set_faction(dog, "Player");
+    world.run_systems(RunState::PreRun);
+
+    step_right(&mut world);
+
+    assert!(!matches!(world.ai_state(dog), AiState::Search { .. }));
+}
+
+#[test]
+fn searching_finds_hidden_traps() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let trap = spawn_trap(&mut world, "Bear Trap", 3, 1);
+    world.run_systems(RunState::PreRun);
+
+    for _ in 0..100 {
+        if !is_hidden(&world, trap) {
+            break;
+        }
+        assert!(player::search(&mut world.ecs) == RunState::PlayerTurn);
+    }
+
+    assert!(!is_hidden(&world, trap));
+    assert!(world.log().contains(&"You spot a Bear Trap.".to_string()));
+}
+
+#[test]
+fn player_notices_hidden_traps_in_sight_over_time() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    let trap = spawn_trap(&mut world, "Bear Trap", 3, 1);
+    world.run_systems(RunState::PreRun);
+
+    for _ in 0..500 {
+        if !is_hidden(&world, trap) {
+            break;
+        }
+        world.run_until_player_turn();
+        world.run_systems(RunState::PlayerTurn);
+    }
+
+    assert!(!is_hidden(&world, trap));
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::TestWorld;
+use rltk::Point;
+use rogue_like::autopilot;
+use rogue_like::{spawner, CombatStats, Position, RunState};
+use specs::prelude::*;
+
+const CORRIDOR: [&str; 3] = [
+    "################################",
+    "#@.............................#",
+    "################################",
+];
+
+/// Starts travelling to `(x, y)` and keeps going until the autopilot hands
+/// control back. Returns the steps taken.
+fn travel(world: &mut TestWorld, x: i32, y: i32) -> i32 {
+    let mut steps = 0;
+    let mut runstate = autopilot::start_travel(&mut world.ecs, Point::new(x, y));
+    while runstate == RunState::PlayerTurn {
+        steps += 1;
+        assert!(steps < 200, "The autopilot never stopped");
+        world.run_systems(RunState::PlayerTurn);
+        world.run_until_player_turn();
+        runstate = autopilot::step(&mut world.ecs).unwrap_or(RunState::AwaitingInput);
+    }
+    steps
+}
+
+#[test]
+fn travel_walks_to_the_clicked_tile() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    world.run_systems(RunState::PreRun);
+
+    assert_eq!(travel(&mut world, 6, 1), 5);
+    assert_eq!(world.position(world.player), (6, 1));
+    assert!(!autopilot::is_active(&world.ecs));
+}
+
+#[test]
+fn travel_refuses_unrevealed_tiles() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    world.run_systems(RunState::PreRun);
+
+    assert_eq!(travel(&mut world, 30, 1), 0);
+    assert_eq!(world.position(world.player), (1, 1));
+    assert_eq!(world.log()[0], "You can't travel there.");
+}
+
+#[test]
+fn travel_stops_when_a_monster_comes_into_view() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    world.spawn_monster("Goblin", 16, 1, 10, 0, 4);
+    world.run_systems(RunState::PreRun);
+
+    travel(&mut world, 9, 1);
+
+    assert!(world.position(world.player).0 < 9);
+    assert_eq!(world.log()[0], "You spot a Goblin.");
+    assert!(!autopilot::is_active(&world.ecs));
+}
+
+#[test]
+fn travel_stops_when_the_player_is_hurt() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    world.run_systems(RunState::PreRun);
+
+    let runstate = autopilot::start_travel(&mut world.ecs, Point::new(6, 1));
+    assert!(runstate == RunState::PlayerTurn);
+    world.run_systems(RunState::PlayerTurn);
+    world.run_until_player_turn();
+
+    world
+        .ecs
+        .write_storage::<CombatStats>()
+        .get_mut(world.player)
+        .unwrap()
+        .hp -= 5;
+
+    assert!(autopilot::step(&mut world.ecs).is_none());
+    assert_eq!(world.position(world.player), (2, 1));
+    assert_eq!(world.log()[0], "You stop travelling; something hurt you.");
+}
+
+#[test]
+fn travel_stops_when_the_player_is_knocked_off_the_path() {
+    let mut world = TestWorld::new(&CORRIDOR);
+    world.run_systems(RunState::PreRun);
+
+    let runstate = autopilot::start_travel(&mut world.ecs, Point::new(6, 1));
+    assert!(runstate == RunState::PlayerTurn);
+    world.run_systems(RunState::PlayerTurn);
+    world.run_until_player_turn();
+
+    // Put the player somewhere the next step isn't next to
+    world
+        .ecs
+        .write_storage::<Position>()
+        .insert(world.player, Position { x: 5, y: 1 })
+        .unwrap();
+    *world.ecs.write_resource::<Point>() = Point::new(5, 1);
+
+    assert!(autopilot::step(&mut world.ecs).is_none());
+    assert_eq!(world.position(world.player), (5, 1));
+    assert_eq!(world.log()[0], "You have lost your way.");
+}
+
+#[test]
+fn travel_stops_when_a_teleport_trap_moves_the_player() {
+    // Wherever the trap sends the player, the rest of the path is out of reach
+    let mut world = TestWorld::new(&["#####", "#@..#", "#####"]);
+    spawner::spawn_named_trap(&mut world.ecs, "Teleport Trap", 2, 1).unwrap();
+    world.run_systems(RunState::PreRun);
+
+    let runstate = autopilot::start_travel(&mut world.ecs, Point::new(3, 1));
+    assert!(runstate == RunState::PlayerTurn);
+    world.run_systems(RunState::PlayerTurn);
+    let landed = world.position(world.player);
+    assert_ne!(landed, (2, 1));
+    world.run_until_player_turn();
+
+    assert!(autopilot::step(&mut world.ecs).is_none());
+    assert_eq!(world.position(world.player), landed);
+    assert_eq!(world.log()[0], "You have lost your way.");
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use common::TestWorld;
+use rogue_like::RunState;
+use specs::prelude::*;
+
+const ROOMS: [&str; 5] = [
+    "###########",
+    "#@...#....#",
+    "#....#....#",
+    "#.........#",
+    "###########",
+];
+
+#[test]
+fn player_sees_their_room() {
+    let mut world = TestWorld::new(&ROOMS);
+    world.run_systems(RunState::PreRun);
+
+    let map = world.map();
+    assert!(map.visible_tiles[map.xy_idx(4, 1)]);
+    assert!(map.revealed_tiles[map.xy_idx(4, 1)]);
+}
+
+#[test]
+fn walls_block_sight() {
+    let mut world = TestWorld::new(&ROOMS);
+    world.run_systems(RunState::PreRun);
+
+    let map = world.map();
+    assert!(!map.visible_tiles[map.xy_idx(7, 1)]);
+    assert!(!map.revealed_tiles[map.xy_idx(7, 1)]);
+}
+
+#[test]
+fn revealed_tiles_stay_revealed_after_moving_away() {
+    let mut world = TestWorld::new(&[
+        "##########",
+        "#@.......#",
+        "########.#",
+        "#........#",
+        "##########",
+    ]);
+    world.run_systems(RunState::PreRun);
+    let start_view = world.map();
+    assert!(start_view.visible_tiles[start_view.xy_idx(2, 1)]);
+
+    // Teleport around the corner and look again
+    {
+        let mut positions = world.ecs.write_storage::<rogue_like::Position>();
+        let pos = positions.get_mut(world.player).unwrap();
+        pos.x = 1;
+        pos.y = 3;
+        let mut viewsheds = world.ecs.write_storage::<rogue_like::Viewshed>();
+        viewsheds.get_mut(world.player).unwrap().dirty = true;
+    }
+    world.run_systems(RunState::PreRun);
+
+    let map = world.map();
+    assert!(!map.visible_tiles[map.xy_idx(2, 1)]);
+    assert!(map.revealed_tiles[map.xy_idx(2, 1)]);
+}